@@ -42,7 +42,7 @@ impl Automat {
             stage3: VecDeque::new(),
             stage4: VecDeque::new(),
             stage5: VecDeque::new(),
-            progress: db::load(&fs::get_progress_db_path()),
+            progress: db::load(&fs::get_progress_db_path()).unwrap_or_default(),
         }
     }
 
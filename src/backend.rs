@@ -0,0 +1,543 @@
+//! Abstracts the terminal primitives `Display` needs behind a `Backend` trait, so the
+//! display logic can be driven by a real terminal or by a scripted test double.
+
+use crate::constants::{PROGRAM_PEEK_KEY, PROGRAM_QUIT_KEY, POLL_RATE_MS, TICK_RATE_MS};
+
+pub use crossterm::Color;
+pub use crossterm::ClearType;
+
+use crossterm::{
+    AlternateScreen, AsyncReader, Colored, InputEvent, KeyEvent, RawScreen, Terminal,
+    TerminalCursor, TerminalInput,
+};
+
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A terminal event `Display` reacts to.
+#[derive(Clone, Debug)]
+pub enum BackendEvent
+{
+    /// A key was pressed.
+    Key(KeyEvent),
+    /// The terminal was resized to the given `(width, height)`.
+    Resize(u16, u16),
+    /// A periodic heartbeat, fired roughly every `TICK_RATE_MS`, that lets an otherwise
+    /// blocking input loop animate (countdowns, flashing feedback) while it waits for a
+    /// keystroke.
+    Tick,
+    /// A whole block of text, delivered atomically, that the terminal received between a
+    /// bracketed-paste start and end marker.
+    Paste(String),
+}
+
+/// The escape sequence a bracketed-paste aware terminal sends right before the pasted
+/// text, once bracketed paste mode has been enabled.
+const PASTE_START: &str = "\x1b[200~";
+
+/// The escape sequence a bracketed-paste aware terminal sends right after the pasted
+/// text.
+const PASTE_END: &str = "\x1b[201~";
+
+/// Enables bracketed paste mode: the terminal wraps any pasted text in `PASTE_START`/
+/// `PASTE_END` markers instead of replaying it as ordinary keystrokes.
+const ENABLE_BRACKETED_PASTE: &str = "\x1b[?2004h";
+
+/// Disables bracketed paste mode again.
+const DISABLE_BRACKETED_PASTE: &str = "\x1b[?2004l";
+
+/// Recognizes `PASTE_START`/`PASTE_END` markers in a stream of raw characters, one at a
+/// time, and assembles the text in between into a single paste.
+///
+/// This crossterm version hands every byte of a paste back through the same keyboard
+/// event stream as ordinary keystrokes, so the markers have to be picked out by hand
+/// instead of relying on a dedicated paste event.
+#[derive(Default)]
+struct PasteDetector
+{
+    active: bool,
+    buffer: String,
+}
+
+impl PasteDetector
+{
+    /// Returns `true` while a paste is currently being assembled, so the caller knows to
+    /// swallow the underlying keystrokes instead of forwarding them.
+    fn is_active(&self) -> bool
+    {
+        self.active
+    }
+
+    /// Feeds one raw character from the input stream into the detector.
+    ///
+    /// Returns the fully assembled paste text once the closing marker has been seen.
+    fn feed(&mut self, ch: char) -> Option<String>
+    {
+        self.buffer.push(ch);
+
+        if !self.active {
+            if self.buffer.ends_with(PASTE_START) {
+                self.active = true;
+                self.buffer.clear();
+            } else if self.buffer.len() > PASTE_START.len() {
+                let excess = self.buffer.len() - PASTE_START.len();
+                self.buffer.drain(..excess);
+            }
+            return None;
+        }
+
+        if self.buffer.ends_with(PASTE_END) {
+            self.active = false;
+            let text_len = self.buffer.len() - PASTE_END.len();
+            let text = self.buffer[..text_len].to_string();
+            self.buffer.clear();
+            return Some(text);
+        }
+
+        None
+    }
+}
+
+/// Returns the raw character a keyboard event represents, if it is one that can appear
+/// inside a bracketed-paste marker.
+fn key_to_raw_char(key: &KeyEvent) -> Option<char>
+{
+    match key {
+        KeyEvent::Char(c) => Some(*c),
+        KeyEvent::Esc => Some('\x1b'),
+        _ => None,
+    }
+}
+
+/// The terminal primitives `Display` is built on: moving the cursor, hiding/showing
+/// it, clearing regions, writing text, reading the terminal size, and reading the next
+/// input event.
+pub trait Backend
+{
+    /// Enters the backend's drawing mode (alternate screen and/or raw mode).
+    fn enter(&mut self) -> io::Result<()>;
+
+    /// Leaves the backend's drawing mode, restoring the terminal to how it was found.
+    fn leave(&mut self) -> io::Result<()>;
+
+    /// Returns the current `(width, height)` of the terminal.
+    fn size(&self) -> (u16, u16);
+
+    /// Moves the cursor to `(x, y)`.
+    fn goto(&mut self, x: u16, y: u16) -> io::Result<()>;
+
+    /// Hides the cursor.
+    fn hide_cursor(&mut self) -> io::Result<()>;
+
+    /// Shows the cursor.
+    fn show_cursor(&mut self) -> io::Result<()>;
+
+    /// Clears the given region of the terminal.
+    fn clear(&mut self, kind: ClearType) -> io::Result<()>;
+
+    /// Writes `text` at the current cursor position, in the given colors.
+    fn write(&mut self, text: &str, fg: Color, bg: Color) -> io::Result<()>;
+
+    /// Returns the next input event, or `None` when the backend has none to give.
+    ///
+    /// `CrosstermBackend` blocks until an event arrives; `TestBackend` returns `None`
+    /// at once when its scripted queue is empty.
+    fn read_event(&mut self) -> Option<BackendEvent>;
+}
+
+/// The `Backend` used in production: a real terminal, driven through crossterm.
+pub struct CrosstermBackend
+{
+    terminal: Terminal,
+    cursor: TerminalCursor,
+    input: TerminalInput,
+    /// Receives `Key`/`Tick` events forwarded by the background event thread spawned in
+    /// `enter()`.
+    rx: Option<mpsc::Receiver<BackendEvent>>,
+    #[cfg(not(debug_assertions))]
+    _alt: Option<AlternateScreen>,
+    #[cfg(debug_assertions)]
+    _raw: Option<RawScreen>,
+}
+
+impl CrosstermBackend
+{
+    /// Creates a new crossterm-backed terminal. Call `enter()` before drawing.
+    pub fn new() -> Self
+    {
+        Self {
+            terminal: crossterm::terminal(),
+            cursor: crossterm::cursor(),
+            input: crossterm::input(),
+            rx: None,
+            #[cfg(not(debug_assertions))]
+            _alt: None,
+            #[cfg(debug_assertions)]
+            _raw: None,
+        }
+    }
+}
+
+impl Backend for CrosstermBackend
+{
+    fn enter(&mut self) -> io::Result<()>
+    {
+        #[cfg(not(debug_assertions))]
+        {
+            self._alt = Some(AlternateScreen::to_alternate(true)?);
+        }
+        #[cfg(debug_assertions)]
+        {
+            self._raw = Some(RawScreen::into_raw_mode()?);
+        }
+
+        print!("{}", ENABLE_BRACKETED_PASTE);
+
+        let mut reader = self.input.read_async();
+        let (tx, rx) = mpsc::channel();
+        let poll_rate = Duration::from_millis(POLL_RATE_MS);
+        let tick_rate = Duration::from_millis(TICK_RATE_MS);
+
+        // Polls the terminal every `POLL_RATE_MS`, forwarding key presses as they arrive
+        // and a `Tick` every `TICK_RATE_MS`, so `Display`'s otherwise-blocking input
+        // loops can still animate a countdown or auto-fail a stalled card. Keystrokes
+        // wrapped in a bracketed-paste marker are assembled into a single `Paste`
+        // instead of being forwarded one by one.
+        thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            let mut paste = PasteDetector::default();
+
+            loop {
+                if let Some(InputEvent::Keyboard(key)) = reader.next() {
+                    match key_to_raw_char(&key).and_then(|ch| paste.feed(ch)) {
+                        Some(text) => {
+                            if tx.send(BackendEvent::Paste(text)).is_err() {
+                                return;
+                            }
+                        }
+                        None if !paste.is_active() => {
+                            if tx.send(BackendEvent::Key(key)).is_err() {
+                                return;
+                            }
+                        }
+                        None => (), // mid-paste, swallow the raw keystroke
+                    }
+                }
+
+                if last_tick.elapsed() >= tick_rate {
+                    if tx.send(BackendEvent::Tick).is_err() {
+                        return;
+                    }
+                    last_tick = Instant::now();
+                }
+
+                thread::sleep(poll_rate);
+            }
+        });
+
+        self.rx = Some(rx);
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()>
+    {
+        print!("{}", DISABLE_BRACKETED_PASTE);
+        self.show_cursor()?;
+        RawScreen::disable_raw_mode()
+    }
+
+    fn size(&self) -> (u16, u16)
+    {
+        self.terminal.terminal_size()
+    }
+
+    fn goto(&mut self, x: u16, y: u16) -> io::Result<()>
+    {
+        self.cursor.goto(x, y)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()>
+    {
+        self.cursor.hide()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()>
+    {
+        self.cursor.show()
+    }
+
+    fn clear(&mut self, kind: ClearType) -> io::Result<()>
+    {
+        self.terminal.clear(kind)
+    }
+
+    fn write(&mut self, text: &str, fg: Color, bg: Color) -> io::Result<()>
+    {
+        print!(
+            "{}{}{}{}{}",
+            Colored::Bg(bg),
+            Colored::Fg(fg),
+            text,
+            Colored::Fg(Color::Reset),
+            Colored::Bg(Color::Reset)
+        );
+        Ok(())
+    }
+
+    fn read_event(&mut self) -> Option<BackendEvent>
+    {
+        self.rx.as_ref()?.recv().ok()
+    }
+}
+
+/// A recorded terminal cell: the character drawn there and its colors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cell
+{
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Default for Cell
+{
+    fn default() -> Self
+    {
+        Self { ch: ' ', fg: Color::Reset, bg: Color::Reset }
+    }
+}
+
+/// A `Backend` that records drawn cells and feeds scripted key events, so the input
+/// loops can be exercised in tests without a real TTY.
+pub struct TestBackend
+{
+    width: u16,
+    height: u16,
+    cursor: (u16, u16),
+    cells: Vec<Cell>,
+    events: std::collections::VecDeque<BackendEvent>,
+}
+
+impl TestBackend
+{
+    /// Creates a blank `width` by `height` test terminal.
+    pub fn new(width: u16, height: u16) -> Self
+    {
+        Self {
+            width,
+            height,
+            cursor: (0, 0),
+            cells: vec![Cell::default(); width as usize * height as usize],
+            events: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Queues a key event to be returned by the next `read_event()` call.
+    pub fn push_key(&mut self, key: KeyEvent)
+    {
+        self.events.push_back(BackendEvent::Key(key));
+    }
+
+    /// Queues a resize event to be returned by the next `read_event()` call.
+    pub fn push_resize(&mut self, width: u16, height: u16)
+    {
+        self.events.push_back(BackendEvent::Resize(width, height));
+    }
+
+    /// Queues a `Tick` event to be returned by the next `read_event()` call.
+    pub fn push_tick(&mut self)
+    {
+        self.events.push_back(BackendEvent::Tick);
+    }
+
+    /// Queues a pasted block of text to be returned by the next `read_event()` call.
+    pub fn push_paste(&mut self, text: impl Into<String>)
+    {
+        self.events.push_back(BackendEvent::Paste(text.into()));
+    }
+
+    /// Returns the cell drawn at `(x, y)`.
+    pub fn cell(&self, x: u16, y: u16) -> Cell
+    {
+        self.cells[y as usize * self.width as usize + x as usize]
+    }
+}
+
+impl Backend for TestBackend
+{
+    fn enter(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    fn size(&self) -> (u16, u16)
+    {
+        (self.width, self.height)
+    }
+
+    fn goto(&mut self, x: u16, y: u16) -> io::Result<()>
+    {
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    fn clear(&mut self, kind: ClearType) -> io::Result<()>
+    {
+        match kind {
+            ClearType::All => {
+                for cell in &mut self.cells {
+                    *cell = Cell::default();
+                }
+            }
+            _ => {
+                let (x, y) = self.cursor;
+                let start = y as usize * self.width as usize + x as usize;
+                let end = (y as usize + 1) * self.width as usize;
+                for cell in &mut self.cells[start..end] {
+                    *cell = Cell::default();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, text: &str, fg: Color, bg: Color) -> io::Result<()>
+    {
+        let (mut x, y) = self.cursor;
+
+        for ch in text.chars() {
+            if x >= self.width {
+                break;
+            }
+            let index = y as usize * self.width as usize + x as usize;
+            self.cells[index] = Cell { ch, fg, bg };
+            x += 1;
+        }
+
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn read_event(&mut self) -> Option<BackendEvent>
+    {
+        self.events.pop_front()
+    }
+}
+
+/// Returns `true` if `key` is the quit shortcut.
+pub fn is_quit_key(key: &KeyEvent) -> bool
+{
+    matches!(key, KeyEvent::Ctrl(c) if *c == PROGRAM_QUIT_KEY)
+}
+
+/// Returns `true` if `key` is the peek shortcut.
+pub fn is_peek_key(key: &KeyEvent) -> bool
+{
+    matches!(key, KeyEvent::Ctrl(c) if *c == PROGRAM_PEEK_KEY)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_backend_records_written_cells()
+    {
+        let mut backend = TestBackend::new(10, 2);
+
+        backend.goto(2, 0).unwrap();
+        backend.write("hi", Color::Green, Color::Reset).unwrap();
+
+        assert_eq!(Cell { ch: 'h', fg: Color::Green, bg: Color::Reset }, backend.cell(2, 0));
+        assert_eq!(Cell { ch: 'i', fg: Color::Green, bg: Color::Reset }, backend.cell(3, 0));
+    }
+
+    #[test]
+    fn test_backend_clear_all_resets_every_cell()
+    {
+        let mut backend = TestBackend::new(4, 1);
+        backend.write("abcd", Color::Red, Color::Reset).unwrap();
+
+        backend.clear(ClearType::All).unwrap();
+
+        assert_eq!(Cell::default(), backend.cell(0, 0));
+    }
+
+    #[test]
+    fn test_backend_replays_scripted_key_events()
+    {
+        let mut backend = TestBackend::new(4, 1);
+        backend.push_key(KeyEvent::Char('a'));
+        backend.push_key(KeyEvent::Ctrl(PROGRAM_QUIT_KEY));
+
+        assert!(matches!(backend.read_event(), Some(BackendEvent::Key(KeyEvent::Char('a')))));
+        assert!(is_quit_key(&match backend.read_event() {
+            Some(BackendEvent::Key(key)) => key,
+            _ => panic!("expected a key event"),
+        }));
+    }
+
+    #[test]
+    fn test_backend_replays_scripted_tick_events()
+    {
+        let mut backend = TestBackend::new(4, 1);
+        backend.push_key(KeyEvent::Char('a'));
+        backend.push_tick();
+
+        assert!(matches!(backend.read_event(), Some(BackendEvent::Key(KeyEvent::Char('a')))));
+        assert!(matches!(backend.read_event(), Some(BackendEvent::Tick)));
+    }
+
+    #[test]
+    fn test_backend_replays_scripted_paste_events()
+    {
+        let mut backend = TestBackend::new(4, 1);
+        backend.push_paste("hello world");
+
+        match backend.read_event() {
+            Some(BackendEvent::Paste(text)) => assert_eq!("hello world", text),
+            _ => panic!("expected a paste event"),
+        }
+    }
+
+    #[test]
+    fn test_paste_detector_assembles_bracketed_text()
+    {
+        let mut detector = PasteDetector::default();
+
+        for ch in PASTE_START.chars() {
+            assert!(detector.feed(ch).is_none());
+        }
+        assert!(detector.is_active());
+
+        for ch in "hi".chars() {
+            assert!(detector.feed(ch).is_none());
+        }
+
+        let mut pasted = None;
+        for ch in PASTE_END.chars() {
+            pasted = detector.feed(ch);
+        }
+
+        assert_eq!(Some("hi".to_string()), pasted);
+        assert!(!detector.is_active());
+    }
+}
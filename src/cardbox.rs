@@ -3,12 +3,15 @@
 use crate::cardbox_parser;
 use crate::common::fs;
 use crate::common::time;
-use crate::constants::INITIAL_QUEUE_SIZE;
-use crate::db::{self, Stage};
+use crate::config::CardboxConfig;
+use crate::db::{self, ProgressStore, Stage};
 use crate::display::Display;
 use crate::flashcards::Flashcard;
+use crate::scheduler::Scheduler;
+use crate::sync::{self, SyncClient};
 
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
 /// Represents a flashcard with additional metadata.
 #[derive(Debug)]
@@ -22,219 +25,269 @@ pub struct Envelope
     pub timestamp: u64,
 }
 
+/// Errors returned by `Cardbox`, so a caller embedding the library can recover or
+/// display a message instead of the process aborting.
+#[derive(Debug)]
+pub enum CardboxError
+{
+    /// A stage fell outside the configured layout (`1..=stages.len()`).
+    InvalidStage
+    {
+        /// The offending 1-based stage number.
+        stage: usize,
+    },
+    /// The targeted stage had no flashcard to take from.
+    EmptyStage
+    {
+        /// The 1-based stage number that was empty.
+        stage: usize,
+    },
+    /// Loading the progress database failed.
+    DbLoad(db::Error),
+    /// Saving the progress database failed.
+    DbSave(db::Error),
+    /// Pulling remote progress from the configured `SyncClient` failed.
+    SyncPull(sync::Error),
+    /// Pushing local progress to the configured `SyncClient` failed.
+    SyncPush(sync::Error),
+    /// The deck file could not be parsed.
+    Parse(cardbox_parser::ParseError),
+}
+
+impl fmt::Display for CardboxError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            CardboxError::InvalidStage { stage } => write!(f, "invalid stage: {}", stage),
+            CardboxError::EmptyStage { stage } => write!(f, "stage {} is empty", stage),
+            CardboxError::DbLoad(e) => write!(f, "error loading progress database: {}", e),
+            CardboxError::DbSave(e) => write!(f, "error saving progress database: {}", e),
+            CardboxError::SyncPull(e) => write!(f, "error pulling remote progress: {}", e),
+            CardboxError::SyncPush(e) => write!(f, "error pushing remote progress: {}", e),
+            CardboxError::Parse(e) => write!(f, "error parsing deck file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CardboxError {}
+
 /// Represents the current learning progress.
-pub struct Progress(pub usize, pub usize, pub usize, pub usize, pub usize, pub usize);
+pub struct Progress
+{
+    /// Number of flashcards not yet admitted into stage 1.
+    pub new: usize,
+    /// Number of flashcards in each numbered stage, indexed from stage 1.
+    pub stages: Vec<usize>,
+}
 
 /// Represents a cardbox that by some logic deals the flashcards based on the user's
 /// learning progress.
 pub struct Cardbox
 {
     stage0: VecDeque<Flashcard>,
-    stage1: VecDeque<Envelope>,
-    stage2: VecDeque<Envelope>,
-    stage3: VecDeque<Envelope>,
-    stage4: VecDeque<Envelope>,
-    stage5: VecDeque<Envelope>,
+    /// Numbered stages, indexed from 0 (so stage `n` lives at `stages[n - 1]`). The
+    /// stage count and cooldowns are configurable; see `CardboxConfig`.
+    stages: Vec<VecDeque<Envelope>>,
+    /// Seconds a flashcard waits in `stages[i]` before it becomes due again.
+    cooldowns_secs: Vec<u64>,
+    /// Number of flashcards that may be newly admitted into stage 1 at once.
+    initial_queue_size: usize,
     progress: HashMap<u64, Stage>,
+    store: Box<dyn ProgressStore>,
+    /// When set, `init` pulls and merges remote progress before loading flashcards, and
+    /// `save` pushes the merged progress back, so two clients editing the same deck
+    /// converge instead of diverging.
+    sync_client: Option<Box<dyn SyncClient>>,
 }
 
 impl Cardbox
 {
-    /// Creates a new flashcard cardbox.
-    pub fn new() -> Self
+    /// Creates a new flashcard cardbox, loading its progress through `store` and its
+    /// stage layout from the user's cardbox config file (falling back to the built-in
+    /// five-stage box if that file is absent).
+    ///
+    /// Pass `db::FileProgressStore::default()` for the usual on-disk behavior, or any
+    /// other `ProgressStore` (an in-memory store for tests, a networked backend, ...).
+    pub fn new(store: impl ProgressStore + 'static) -> Result<Self, CardboxError>
     {
-        Self {
+        Self::with_config(store, CardboxConfig::load(fs::get_cardbox_config_path()))
+    }
+
+    /// Creates a new flashcard cardbox with an explicit stage layout, bypassing the
+    /// config file. Mainly useful for tests that need a known number of stages.
+    pub fn with_config(
+        store: impl ProgressStore + 'static,
+        config: CardboxConfig,
+    ) -> Result<Self, CardboxError>
+    {
+        let store: Box<dyn ProgressStore> = Box::new(store);
+        let progress = store.load().map_err(CardboxError::DbLoad)?;
+        let stages = config.stage_cooldowns_secs.iter().map(|_| VecDeque::new()).collect();
+
+        Ok(Self {
             stage0: VecDeque::new(),
-            stage1: VecDeque::new(),
-            stage2: VecDeque::new(),
-            stage3: VecDeque::new(),
-            stage4: VecDeque::new(),
-            stage5: VecDeque::new(),
-            progress: db::load(&fs::get_progress_db_path()),
+            stages,
+            cooldowns_secs: config.stage_cooldowns_secs,
+            initial_queue_size: config.initial_queue_size,
+            progress,
+            store,
+            sync_client: None,
+        })
+    }
+
+    /// Configures a `SyncClient` so `init` pulls and merges remote progress before
+    /// loading flashcards, and `save` pushes the merged progress back afterwards.
+    pub fn set_sync_client(&mut self, client: impl SyncClient + 'static)
+    {
+        self.sync_client = Some(Box::new(client));
+    }
+
+    /// Returns the index into `self.stages` for the 1-based `stage`.
+    fn stage_index(&self, stage: usize) -> Result<usize, CardboxError>
+    {
+        if stage == 0 || stage > self.stages.len() {
+            return Err(CardboxError::InvalidStage { stage });
         }
+        Ok(stage - 1)
     }
 
     /// Tries to put the flashcard into the cardbox.
     ///
     /// This will only succeed, if the card was previously taken from the cardbox and put
     /// into the cardbox.
-    pub fn init(&mut self, path: &str)
+    ///
+    /// If a `SyncClient` is configured (see `set_sync_client`), this first pulls the
+    /// remote progress and merges it into `self.progress` (the newer `Stage` per hash
+    /// wins), so two clients editing the same deck converge instead of diverging.
+    pub fn init(&mut self, path: &str) -> Result<(), CardboxError>
     {
-        let flashcards = cardbox_parser::parse_from_file(path);
+        if let Some(client) = &self.sync_client {
+            let remote = client.pull().map_err(CardboxError::SyncPull)?;
+            self.progress = sync::merge(std::mem::take(&mut self.progress), remote);
+        }
+
+        let flashcards = cardbox_parser::parse_from_file(path).map_err(CardboxError::Parse)?;
         // Fill all stages according to the progress database
         for flashcard in flashcards.into_iter() {
             let hash = flashcard.get_hash();
 
             if let Some(stage) = self.progress.get(&hash) {
                 let timestamp = stage.timestamp_ms;
+                let index = stage.index as usize;
 
-                match stage.index {
-                    1 => self.stage1.push_back(Envelope { flashcard, hash, timestamp }),
-                    2 => self.stage2.push_back(Envelope { flashcard, hash, timestamp }),
-                    3 => self.stage3.push_back(Envelope { flashcard, hash, timestamp }),
-                    4 => self.stage4.push_back(Envelope { flashcard, hash, timestamp }),
-                    5 => self.stage5.push_back(Envelope { flashcard, hash, timestamp }),
-                    _ => panic!("error: invalid stage in progress database"),
+                if index == 0 || index > self.stages.len() {
+                    // db::load already migrated the progress database to the current
+                    // format, so this only fires for a stage index this layout's
+                    // config doesn't have (e.g. a shrunk stage count).
+                    return Err(CardboxError::InvalidStage { stage: index });
                 }
+                self.stages[index - 1].push_back(Envelope { flashcard, hash, timestamp });
             } else {
                 self.stage0.push_back(flashcard);
             }
         }
 
-        // If there is still room in Stage 1, then fill it with flashcards from the
-        // cardbox
-        while !self.stage0.is_empty() && self.stage1.len() < INITIAL_QUEUE_SIZE {
+        self.refill_stage1();
+        Ok(())
+    }
+
+    /// Moves flashcards from `stage0` into stage 1 until it reaches
+    /// `initial_queue_size` or `stage0` runs dry.
+    fn refill_stage1(&mut self)
+    {
+        while !self.stage0.is_empty() && self.stages[0].len() < self.initial_queue_size {
             let flashcard = self.stage0.pop_front().unwrap(); // cannot fail
             let hash = flashcard.get_hash();
             let timestamp = time::get_unix_time_millis();
 
-            self.stage1.push_back(Envelope { flashcard, hash, timestamp });
+            self.stages[0].push_back(Envelope { flashcard, hash, timestamp });
         }
     }
 
     /// Increases the stage of the flashcard.
-    pub fn increase_stage(&mut self, current_stage: usize)
+    pub fn increase_stage(&mut self, current_stage: usize) -> Result<(), CardboxError>
     {
-        match current_stage {
-            5 => {
-                // let flashcards stay in the last stage forever
-                let mut envelope = self.stage5.pop_front().unwrap();
-                envelope.timestamp = time::get_unix_time_millis();
-                self.stage5.push_back(envelope);
-            }
-            4 => {
-                let mut envelope = self.stage4.pop_front().unwrap();
-                envelope.timestamp = time::get_unix_time_millis();
-                self.stage5.push_back(envelope);
-            }
-            3 => {
-                let mut envelope = self.stage3.pop_front().unwrap();
-                envelope.timestamp = time::get_unix_time_millis();
-                self.stage4.push_back(envelope);
-            }
-            2 => {
-                let mut envelope = self.stage2.pop_front().unwrap();
-                envelope.timestamp = time::get_unix_time_millis();
-                self.stage3.push_back(envelope);
-            }
-            1 => {
-                let mut envelope = self.stage1.pop_front().unwrap();
-                envelope.timestamp = time::get_unix_time_millis();
-                self.stage2.push_back(envelope);
-
-                // refill stage 1 if necessary
-                while self.stage1.len() < INITIAL_QUEUE_SIZE && !self.stage0.is_empty() {
-                    let flashcard = self.stage0.pop_front().unwrap();
-                    let hash = flashcard.get_hash();
-                    let timestamp = time::get_unix_time_millis();
-                    let envelope = Envelope { flashcard, hash, timestamp };
-                    self.stage1.push_back(envelope);
-                }
-            }
-            _ => panic!("error: invalid stage"),
+        let idx = self.stage_index(current_stage)?;
+        let mut envelope = self.stages[idx]
+            .pop_front()
+            .ok_or(CardboxError::EmptyStage { stage: current_stage })?;
+        envelope.timestamp = time::get_unix_time_millis();
+
+        if idx + 1 == self.stages.len() {
+            // let flashcards stay in the last stage forever
+            self.stages[idx].push_back(envelope);
+        } else {
+            self.stages[idx + 1].push_back(envelope);
         }
+
+        if idx == 0 {
+            self.refill_stage1();
+        }
+
+        Ok(())
     }
 
     /// Resets the stage of the flashcard.
-    pub fn reset_stage(&mut self, current_stage: usize)
+    pub fn reset_stage(&mut self, current_stage: usize) -> Result<(), CardboxError>
     {
-        let mut envelope = match current_stage {
-            5 => self.stage5.pop_front().unwrap(),
-            4 => self.stage4.pop_front().unwrap(),
-            3 => self.stage3.pop_front().unwrap(),
-            2 => self.stage2.pop_front().unwrap(),
-            1 => self.stage1.pop_front().unwrap(),
-            _ => panic!("error: invalid stage"),
-        };
+        let idx = self.stage_index(current_stage)?;
+        let mut envelope = self.stages[idx]
+            .pop_front()
+            .ok_or(CardboxError::EmptyStage { stage: current_stage })?;
         envelope.timestamp = time::get_unix_time_millis();
-        self.stage1.push_back(envelope);
+        self.stages[0].push_back(envelope);
+        Ok(())
     }
 
     /// Saves the progress to the internal key-value store.
-    pub fn save(&mut self)
+    ///
+    /// If a `SyncClient` is configured (see `set_sync_client`), this also pushes the
+    /// saved progress to the remote server afterwards.
+    pub fn save(&self) -> Result<(), CardboxError>
     {
-        for envelope in self.stage1.iter() {
-            let stage = Stage { index: 1, timestamp_ms: envelope.timestamp };
-            self.progress.insert(envelope.hash, stage);
-        }
-        for envelope in self.stage2.iter() {
-            let stage = Stage { index: 2, timestamp_ms: envelope.timestamp };
-            self.progress.insert(envelope.hash, stage);
-        }
-        for envelope in self.stage3.iter() {
-            let stage = Stage { index: 3, timestamp_ms: envelope.timestamp };
-            self.progress.insert(envelope.hash, stage);
-        }
-        for envelope in self.stage4.iter() {
-            let stage = Stage { index: 4, timestamp_ms: envelope.timestamp };
-            self.progress.insert(envelope.hash, stage);
+        let mut progress = self.progress.clone();
+
+        for (i, queue) in self.stages.iter().enumerate() {
+            let index = (i + 1) as u64;
+            for envelope in queue.iter() {
+                let previous = progress.remove(&envelope.hash).unwrap_or_default();
+                let stage = Stage { index, timestamp_ms: envelope.timestamp, ..previous };
+                progress.insert(envelope.hash, stage);
+            }
         }
-        for envelope in self.stage5.iter() {
-            let stage = Stage { index: 5, timestamp_ms: envelope.timestamp };
-            self.progress.insert(envelope.hash, stage);
+        self.store.save(&progress).map_err(CardboxError::DbSave)?;
+
+        if let Some(client) = &self.sync_client {
+            client.push(&progress).map_err(CardboxError::SyncPush)?;
         }
-        db::save(&self.progress, &fs::get_progress_db_path())
-            .expect("error saving database");
+
+        Ok(())
     }
 
     /// Returns the number of flashcards currently being actively processed.
     pub fn num_active(&self) -> usize
     {
-        self.stage1.len()
-            + self.stage2.len()
-            + self.stage3.len()
-            + self.stage4.len()
-            + self.stage5.len()
+        self.stages.iter().map(VecDeque::len).sum()
     }
 
     /// Returns the number of all flashcards in the cardbox.
     pub fn size(&self) -> usize
     {
-        self.stage0.len()
-            + self.stage1.len()
-            + self.stage2.len()
-            + self.stage3.len()
-            + self.stage4.len()
-            + self.stage5.len()
+        self.stage0.len() + self.num_active()
     }
 
     /// Returns the next flashcard and its current stage.
     pub fn next(&self) -> Option<(&Flashcard, usize)>
     {
-        use crate::constants::STAGE1_COOLDOWN;
-        use crate::constants::STAGE2_COOLDOWN;
-        use crate::constants::STAGE3_COOLDOWN;
-        use crate::constants::STAGE4_COOLDOWN;
-        use crate::constants::STAGE5_COOLDOWN;
-
         let current_time = time::get_unix_time_millis();
 
-        if let Some(envelope) = self.stage5.front() {
-            if envelope.timestamp <= (current_time - STAGE5_COOLDOWN * 1000) {
-                return Some((&envelope.flashcard, 5));
-            }
-        }
-
-        if let Some(envelope) = self.stage4.front() {
-            if envelope.timestamp <= (current_time - STAGE4_COOLDOWN * 1000) {
-                return Some((&envelope.flashcard, 4));
-            }
-        }
-
-        if let Some(envelope) = self.stage3.front() {
-            if envelope.timestamp <= (current_time - STAGE3_COOLDOWN * 1000) {
-                return Some((&envelope.flashcard, 3));
-            }
-        }
-
-        if let Some(envelope) = self.stage2.front() {
-            if envelope.timestamp <= (current_time - STAGE2_COOLDOWN * 1000) {
-                return Some((&envelope.flashcard, 2));
-            }
-        }
-        if let Some(envelope) = self.stage1.front() {
-            if envelope.timestamp <= (current_time - STAGE1_COOLDOWN * 1000) {
-                return Some((&envelope.flashcard, 1));
+        for (i, queue) in self.stages.iter().enumerate().rev() {
+            if let Some(envelope) = queue.front() {
+                let cooldown_secs = self.cooldowns_secs[i];
+                if envelope.timestamp <= (current_time - cooldown_secs * 1000) {
+                    return Some((&envelope.flashcard, i + 1));
+                }
             }
         }
 
@@ -249,14 +302,10 @@ impl Cardbox
     /// TODO: remove this methods.
     pub fn progress(&self) -> Progress
     {
-        Progress(
-            self.stage0.len(),
-            self.stage1.len(),
-            self.stage2.len(),
-            self.stage3.len(),
-            self.stage4.len(),
-            self.stage5.len(),
-        )
+        Progress {
+            new: self.stage0.len(),
+            stages: self.stages.iter().map(VecDeque::len).collect(),
+        }
     }
 
     /// Displays the current progress.
@@ -266,32 +315,177 @@ impl Cardbox
     }
 }
 
+impl Scheduler for Cardbox
+{
+    /// # Panics
+    ///
+    /// `Scheduler::init` is infallible by contract; this panics on a `CardboxError`
+    /// instead. Call `Cardbox::init` directly for a `Result`.
+    fn init(&mut self, path: &str)
+    {
+        Cardbox::init(self, path).expect("error initializing cardbox");
+    }
+
+    fn next(&self) -> Option<(&Flashcard, usize)>
+    {
+        Cardbox::next(self)
+    }
+
+    /// # Panics
+    ///
+    /// `Scheduler::grade` is infallible by contract; this panics on a `CardboxError`
+    /// instead. Call `Cardbox::increase_stage`/`reset_stage` directly for a `Result`.
+    fn grade(&mut self, quality: u8)
+    {
+        if let Some((_, current_stage)) = Cardbox::next(self) {
+            let result = if quality >= 3 {
+                self.increase_stage(current_stage)
+            } else {
+                self.reset_stage(current_stage)
+            };
+            result.expect("error grading flashcard");
+        }
+    }
+
+    /// # Panics
+    ///
+    /// `Scheduler::save` is infallible by contract; this panics on a `CardboxError`
+    /// instead. Call `Cardbox::save` directly for a `Result`.
+    fn save(&self)
+    {
+        Cardbox::save(self).expect("error saving database");
+    }
+
+    fn size(&self) -> usize
+    {
+        Cardbox::size(self)
+    }
+}
+
 #[cfg(test)]
 mod tests
 {
     use super::*;
+    use crate::db::Error;
+
+    /// A `ProgressStore` that starts empty and discards whatever is saved to it, so
+    /// tests don't touch the real progress database.
+    struct NullStore;
+
+    impl ProgressStore for NullStore
+    {
+        fn load(&self) -> Result<HashMap<u64, Stage>, Error>
+        {
+            Ok(HashMap::new())
+        }
+
+        fn save(&self, _progress: &HashMap<u64, Stage>) -> Result<(), Error>
+        {
+            Ok(())
+        }
+    }
 
     #[test]
     fn new_cardbox()
     {
-        let cardbox = Cardbox::new();
+        let cardbox = Cardbox::new(NullStore).expect("error creating cardbox");
         assert_eq!(0, cardbox.stage0.len());
-        assert_eq!(0, cardbox.stage1.len());
+        assert_eq!(0, cardbox.stages[0].len());
     }
 
     #[test]
     fn init_cardbox()
     {
-        let mut cardbox = Cardbox::new();
+        let store = db::FileProgressStore::new("./sample_db.txt");
+        let mut cardbox = Cardbox::new(store).expect("error creating cardbox");
 
-        cardbox.init("./sample_box.txt");
+        cardbox.init("./sample_box.txt").expect("error initializing cardbox");
         assert_eq!(32, cardbox.size());
         assert_eq!(20, cardbox.num_active());
         assert_eq!(12, cardbox.stage0.len());
-        assert_eq!(10, cardbox.stage1.len());
-        assert_eq!(2, cardbox.stage2.len());
-        assert_eq!(4, cardbox.stage3.len());
-        assert_eq!(3, cardbox.stage4.len());
-        assert_eq!(1, cardbox.stage5.len());
+        assert_eq!(10, cardbox.stages[0].len());
+        assert_eq!(2, cardbox.stages[1].len());
+        assert_eq!(4, cardbox.stages[2].len());
+        assert_eq!(3, cardbox.stages[3].len());
+        assert_eq!(1, cardbox.stages[4].len());
+    }
+
+    #[test]
+    fn a_3_stage_box_never_advances_past_its_last_stage()
+    {
+        let config = CardboxConfig { stage_cooldowns_secs: vec![0, 0, 0], initial_queue_size: 1 };
+        let mut cardbox =
+            Cardbox::with_config(NullStore, config).expect("error creating cardbox");
+
+        cardbox.init("./sample_box.txt").expect("error initializing cardbox");
+        assert_eq!(3, cardbox.stages.len());
+
+        for _ in 0..5 {
+            let (_, stage) = cardbox.next().expect("a due flashcard");
+            cardbox.increase_stage(stage).expect("error increasing stage");
+        }
+
+        assert_eq!(3, cardbox.next().expect("a due flashcard").1);
+    }
+
+    #[test]
+    fn increase_stage_on_an_empty_stage_returns_an_error()
+    {
+        let mut cardbox = Cardbox::new(NullStore).expect("error creating cardbox");
+        assert!(matches!(
+            cardbox.increase_stage(1),
+            Err(CardboxError::EmptyStage { stage: 1 })
+        ));
+    }
+
+    #[test]
+    fn increase_stage_with_an_out_of_range_stage_returns_an_error()
+    {
+        let mut cardbox = Cardbox::new(NullStore).expect("error creating cardbox");
+        let stages = cardbox.stages.len();
+        assert!(matches!(
+            cardbox.increase_stage(stages + 1),
+            Err(CardboxError::InvalidStage { stage }) if stage == stages + 1
+        ));
+    }
+
+    /// A `SyncClient` stub that always pulls a fixed remote progress database and
+    /// records whatever gets pushed to it.
+    struct StubSyncClient
+    {
+        remote: HashMap<u64, Stage>,
+        pushed: std::sync::Mutex<Option<HashMap<u64, Stage>>>,
+    }
+
+    impl crate::sync::SyncClient for StubSyncClient
+    {
+        fn push(&self, db: &HashMap<u64, Stage>) -> Result<(), crate::sync::Error>
+        {
+            *self.pushed.lock().unwrap() = Some(db.clone());
+            Ok(())
+        }
+
+        fn pull(&self) -> Result<HashMap<u64, Stage>, crate::sync::Error>
+        {
+            Ok(self.remote.clone())
+        }
+    }
+
+    #[test]
+    fn init_merges_in_remote_progress_from_the_sync_client()
+    {
+        // A hash only the remote side knows about; it need not match a real flashcard in
+        // `sample_box.txt` for this test, since merging happens before the deck is read.
+        let hash = 999_999;
+
+        let mut remote = HashMap::new();
+        remote.insert(hash, Stage { index: 2, timestamp_ms: 0, ..Stage::default() });
+
+        let client = StubSyncClient { remote, pushed: std::sync::Mutex::new(None) };
+        let mut cardbox = Cardbox::new(NullStore).expect("error creating cardbox");
+        cardbox.set_sync_client(client);
+
+        cardbox.init("./sample_box.txt").expect("error initializing cardbox");
+        assert!(cardbox.progress.contains_key(&hash));
     }
 }
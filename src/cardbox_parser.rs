@@ -1,11 +1,10 @@
 //! Functionality for parsing flashcard text files.
 
-use crate::constants::{MARKUP, MARKUP_COMMENT, MARKUP_ESCAPE, MARKUP_FACE, MARKUP_NOTE};
 use crate::flashcards::flashcard_factory::FlashcardFactory;
 use crate::flashcards::*;
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt;
+use std::io::{BufRead, Read};
 use std::path::Path;
 
 /// A parser state machine.
@@ -16,18 +15,13 @@ enum ParserState
     Face,
     Back,
     Note,
+    /// Inside a verbatim block (`MARKUP_FENCE` opened it): every line is pushed to
+    /// the card's back as-is until a matching fence line closes it.
+    Fenced,
 }
 
 impl ParserState
 {
-    pub fn move_to(&mut self, state: ParserState)
-    {
-        if !self.can_move_to(&state) {
-            panic!("cannot parse file");
-        }
-        *self = state;
-    }
-
     fn can_move_to(&self, next_state: &ParserState) -> bool
     {
         match *self {
@@ -36,24 +30,35 @@ impl ParserState
                 ParserState::Face => true,
                 ParserState::Back => false,
                 ParserState::Note => false,
+                ParserState::Fenced => false,
             },
             ParserState::Face => match *next_state {
                 ParserState::Init => true,
                 ParserState::Face => false,
                 ParserState::Back => true,
                 ParserState::Note => false,
+                ParserState::Fenced => true,
             },
             ParserState::Back => match *next_state {
                 ParserState::Init => true,
                 ParserState::Face => true,
                 ParserState::Back => true,
                 ParserState::Note => true,
+                ParserState::Fenced => true,
             },
             ParserState::Note => match *next_state {
                 ParserState::Init => true,
                 ParserState::Face => true,
                 ParserState::Back => false,
                 ParserState::Note => false,
+                ParserState::Fenced => false,
+            },
+            ParserState::Fenced => match *next_state {
+                ParserState::Init => false,
+                ParserState::Face => false,
+                ParserState::Back => true,
+                ParserState::Note => false,
+                ParserState::Fenced => false,
             },
         }
     }
@@ -66,159 +71,567 @@ impl ParserState
                 ParserState::Face => true,
                 ParserState::Back => false,
                 ParserState::Note => false,
+                ParserState::Fenced => false,
             },
             ParserState::Note => match *next_state {
                 ParserState::Init => true,
                 ParserState::Face => true,
                 ParserState::Back => false,
                 ParserState::Note => false,
+                ParserState::Fenced => false,
             },
             _ => false,
         }
     }
 }
 
+/// A recoverable parsing failure, located by the subject file it came from and the
+/// 1-based line/column where it was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError
+{
+    /// The subject name of the file being parsed when the error occurred.
+    pub file: String,
+    /// The 1-based line number of the offending line.
+    pub line: usize,
+    /// The 1-based column of the offending line.
+    pub col: usize,
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.col, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The kind of malformed input a `ParseError` describes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind
+{
+    /// A line appeared where the parser's state machine doesn't allow it, e.g. a note
+    /// before any face line, or back lines with no face line opened yet.
+    UnexpectedMarker,
+    /// A face marker (`#`/`##`) wasn't followed by any front text.
+    MissingFace,
+    /// The face marker's `#`-count doesn't match any known flashcard type.
+    UnsupportedCardType,
+    /// The input ended without ever opening a flashcard, so there was nothing to build.
+    UnterminatedCard,
+    /// The input ended while still inside a verbatim block (`MARKUP_FENCE` opened one
+    /// that was never closed).
+    UnterminatedFence,
+    /// A face, note, or back line wasn't valid UTF-8.
+    InvalidUtf8,
+    /// The deck file itself couldn't be read.
+    Io(String),
+}
+
+impl fmt::Display for ParseErrorKind
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            ParseErrorKind::UnexpectedMarker => {
+                write!(f, "this line is not valid in the current parser state")
+            }
+            ParseErrorKind::MissingFace => write!(f, "flashcard front text is missing"),
+            ParseErrorKind::UnsupportedCardType => write!(f, "flashcard type is not supported"),
+            ParseErrorKind::UnterminatedCard => {
+                write!(f, "reached end of file without a complete flashcard")
+            }
+            ParseErrorKind::UnterminatedFence => {
+                write!(f, "reached end of file with an unclosed verbatim block")
+            }
+            ParseErrorKind::InvalidUtf8 => write!(f, "line is not valid UTF-8"),
+            ParseErrorKind::Io(message) => write!(f, "error reading deck file: {}", message),
+        }
+    }
+}
+
 /// Parses a cardbox from the given file.
-pub fn parse_from_file(path: &str) -> Vec<Flashcard>
+///
+/// Unlike `parse_bytes`, which accumulates every recoverable diagnostic it finds, this
+/// reports only the first error: a single `ParseError` is enough for a caller that
+/// just wants to know whether it can proceed with the parsed deck.
+pub fn parse_from_file(path: &str) -> Result<Vec<Flashcard>, ParseError>
 {
     let path = Path::new(path);
-    let file = File::open(&path).unwrap();
-    let buff = BufReader::new(file);
-    let name = path.file_name().unwrap().to_str().unwrap();
-    parse(buff, name)
+    let io_err = |message: String| ParseError {
+        file: path.display().to_string(),
+        line: 0,
+        col: 0,
+        kind: ParseErrorKind::Io(message),
+    };
+
+    let src = std::fs::read(&path).map_err(|e| io_err(e.to_string()))?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io_err(format!("{} has no valid file name", path.display())))?;
+
+    parse_bytes(&src, name).map_err(|mut errors| errors.remove(0))
+}
+
+/// Parses and concatenates several files into one cardbox.
+///
+/// Each file is parsed independently via `parse_from_file` and keeps its own
+/// file-derived subject name, so a study session can be assembled from several topic
+/// files at once, the same way the old `fileinput` Python module chains multiple
+/// inputs into one logical stream. Stops at the first file that fails to parse; the
+/// returned `ParseError` identifies which file and line that was.
+pub fn parse_from_files(paths: &[&str]) -> Result<Vec<Flashcard>, ParseError>
+{
+    let mut flashcards = vec![];
+    for path in paths {
+        flashcards.extend(parse_from_file(path)?);
+    }
+    Ok(flashcards)
 }
 
 /// Parses a cardbox from the given reader and a subject name.
-pub fn parse(buff: impl BufRead, name: &str) -> Vec<Flashcard>
+///
+/// A thin wrapper around `parse_bytes` for callers that only have a `BufRead`, e.g. a
+/// test fixture or a reader that isn't backed by a plain file. Prefer `parse_bytes`
+/// directly when the whole input is already in memory, to skip this extra copy.
+pub fn parse(mut buff: impl BufRead, name: &str) -> Result<Vec<Flashcard>, Vec<ParseError>>
+{
+    let mut src = Vec::new();
+    if let Err(e) = buff.read_to_end(&mut src) {
+        return Err(vec![ParseError {
+            file: name.to_string(),
+            line: 0,
+            col: 0,
+            kind: ParseErrorKind::Io(e.to_string()),
+        }]);
+    }
+
+    parse_bytes(&src, name)
+}
+
+/// Parses and concatenates several readers into one cardbox, each tagged with its own
+/// subject name.
+///
+/// The counterpart to `parse_from_files` for callers that already have readers in
+/// memory rather than paths on disk, e.g. deck content fetched over the network.
+/// `readers` and `names` are paired up by position; stops at the first reader that
+/// fails to parse.
+pub fn parse_many(readers: Vec<Box<dyn BufRead>>, names: &[&str]) -> Result<Vec<Flashcard>, ParseError>
+{
+    let mut flashcards = vec![];
+    for (reader, name) in readers.into_iter().zip(names.iter()) {
+        let cards = parse(reader, name).map_err(|mut errors| errors.remove(0))?;
+        flashcards.extend(cards);
+    }
+    Ok(flashcards)
+}
+
+/// Parses a cardbox directly from raw bytes.
+///
+/// UTF-8 is only validated for the spans that actually become card data (face text,
+/// note text, retained back content); comment and blank lines, and the bytes consumed
+/// by markup, are classified without ever being decoded. For a multi-thousand-card
+/// deck, most lines are comments or blanks, so this noticeably cuts the allocation and
+/// validation cost `parse`'s old line-by-line `String` scan paid on every line.
+///
+/// Parsing never stops at the first mistake: a malformed card is skipped and its
+/// error recorded, so a caller editing a large deck sees every mistake in one pass
+/// instead of fixing and re-running one panic at a time.
+pub fn parse_bytes(src: &[u8], name: &str) -> Result<Vec<Flashcard>, Vec<ParseError>>
 {
     use crate::constants::*;
     use crate::flashcards::FlashcardBack::*;
+    use crate::lexer::{self, TokenKind};
 
     let mut flashcards = vec![];
+    let mut errors = vec![];
     let mut state = ParserState::Init;
     let mut factory = FlashcardFactory::new(name);
     let mut card_back = None;
+    // Set once a card is discarded after an error, so the rest of its lines are
+    // dropped with it until the next face marker starts a new card.
+    let mut skipping = false;
+
+    // Builds a `ParseError` tagged with the subject file currently being parsed.
+    let err = |line: usize, col: usize, kind: ParseErrorKind| ParseError {
+        file: name.to_string(),
+        line,
+        col,
+        kind,
+    };
 
-    for line in buff.lines().filter_map(|r| r.ok()) {
-        let line = line.trim();
-        if line.is_empty() {
+    // Discards whatever card is in progress and returns the parser to a state from
+    // which scanning can resume on the next line.
+    macro_rules! recover {
+        () => {{
+            state = ParserState::Init;
+            factory = FlashcardFactory::new(name);
+            card_back = None;
+            skipping = true;
+        }};
+    }
+
+    let mut line_no = 0;
+    let mut pos = 0;
+    while pos < src.len() {
+        let line_end = memchr::memchr(b'\n', &src[pos..]).map_or(src.len(), |i| pos + i);
+        let raw_line = strip_trailing_cr(&src[pos..line_end]);
+        line_no += 1;
+        pos = line_end + 1;
+
+        // Inside a fence, every line is pushed to the card's back untouched, except
+        // for the closing fence itself: no lexing, trimming, or escape processing.
+        if state == ParserState::Fenced {
+            if is_fence_line(raw_line) {
+                state = ParserState::Back;
+                continue;
+            }
+
+            let text = match std::str::from_utf8(raw_line) {
+                Ok(text) => text,
+                Err(_) => {
+                    errors.push(err(line_no, 1, ParseErrorKind::InvalidUtf8));
+                    recover!();
+                    continue;
+                }
+            };
+
+            match card_back.as_mut() {
+                Some(WriteTheLine(lines)) => {
+                    lines.push(TypedLine { text: text.to_string(), converter: None })
+                }
+                Some(FillTheBlank(lines)) => lines.push(vec![LinePart(text.to_string(), false, 0)]),
+                None => (), // unreachable: Fenced is only entered once a card back exists
+            }
             continue;
         }
-        //println!("{}", line);
 
-        // 1st char must exist, so unwrap won't fail ever
-        let first_char = line.chars().nth(0).unwrap();
+        let token = match lexer::tokenize_bytes(line_no, raw_line) {
+            Ok(token) => token,
+            Err(_) => {
+                errors.push(err(line_no, 1, ParseErrorKind::InvalidUtf8));
+                recover!();
+                continue;
+            }
+        };
+
+        match token.kind {
+            TokenKind::Blank => continue,
+
+            TokenKind::Comment => (), // Ignore this line
+
+            TokenKind::FaceMarker { level, text } => {
+                skipping = false;
 
-        match first_char {
-            MARKUP_FACE => {
                 if state.can_build(&ParserState::Face) {
                     let back = std::mem::replace(&mut card_back, None).unwrap();
                     factory.set_back(back);
                     flashcards.push(factory.build());
                 }
 
-                state.move_to(ParserState::Face);
-
-                if line.len() < 2 {
-                    panic!("error: no flashcard front specified");
+                if !state.can_move_to(&ParserState::Face) {
+                    errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                    recover!();
+                    continue;
                 }
+                state = ParserState::Face;
 
-                // Determine the type of flashcard according to the number '#'
-                let count = line.chars().take_while(|c| c == &MARKUP_FACE).count();
-                let face_text = line.chars().skip(count).collect::<String>();
-                //println!("count = {}, face_text = {}", count, face_text);
+                // A bare face marker with nothing after it has no front text at all.
+                if text.is_empty() {
+                    errors.push(err(token.line, token.col, ParseErrorKind::MissingFace));
+                    recover!();
+                    continue;
+                }
 
-                factory.set_face(face_text.trim());
+                factory.set_face(text);
 
-                card_back = match count {
+                // Determine the type of flashcard according to the number of '#'
+                card_back = match level {
                     TYPE_WRITE_THE_LINE => Some(WriteTheLine(vec![])),
                     TYPE_FILL_THE_BLANK => Some(FillTheBlank(vec![])),
-                    _ => panic!("flashcard type is not supported"),
+                    _ => {
+                        errors.push(err(token.line, token.col, ParseErrorKind::UnsupportedCardType));
+                        recover!();
+                        continue;
+                    }
                 };
             }
 
-            MARKUP_NOTE => {
-                state.move_to(ParserState::Note);
-
-                let note_text = line.split(MARKUP_NOTE).nth(1).unwrap().trim();
+            TokenKind::Note(note_text) => {
+                if skipping {
+                    continue;
+                }
+                if !state.can_move_to(&ParserState::Note) {
+                    errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                    recover!();
+                    continue;
+                }
+                state = ParserState::Note;
 
                 factory.set_note(note_text);
             }
 
-            MARKUP_COMMENT => (), // Ignore this line
-
-            _ => {
-                let data = {
-                    // If the 1st character is the `Escape` character, and actually used
-                    // for escaping a markup char ...
-                    if first_char == MARKUP_ESCAPE
-                        && if let Some(c) = line.chars().nth(1) {
-                            MARKUP.contains(&c)
-                        } else {
-                            false
-                        }
-                    {
-                        // ... then remove it, and treat the rest of the line as part of
-                        // the flashcard data
-                        line.chars().skip(1).collect::<String>()
-                    } else {
-                        line.to_string()
+            TokenKind::Content(data) => {
+                if card_back.is_none() {
+                    if skipping {
+                        continue;
                     }
-                };
+                    errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                    recover!();
+                    continue;
+                }
 
                 // Depending on the card type parse the back of the flashcard
-                match card_back.as_mut() {
-                    Some(v) => match v {
-                        WriteTheLine(lines) => {
-                            //println!("Pushing data = {}", data);
-                            lines.push(data);
-                        }
-                        FillTheBlank(lines) => {
-                            // UPPERCASE indicates a blank
-                            // '<','>' alternative way to indicate a blank
-                            // '_' indicates that the following letter has to be entered
-                            //      as uppercase as well
-                            // '\' indicates that the following uppercase word is not
-                            // actually a blank
-                            let mut parts: LineWithBlanks = vec![];
-                            let mut index = 0;
-
-                            for part in data.split_whitespace() {
-                                //
-                                let blank = part
-                                    .chars()
-                                    .all(|c| c.is_uppercase() || !c.is_alphanumeric());
-
-                                let p = if blank {
-                                    String::from(part.to_lowercase())
-                                } else {
-                                    String::from(part)
-                                };
-
-                                parts.push(LinePart(p, blank, index));
-
-                                index += part.len() + 1;
+                match card_back.as_mut().unwrap() {
+                    WriteTheLine(lines) => {
+                        // A line may end in `@<type>` (e.g. `7@int`,
+                        // `2024-01-02@timestamp|%Y-%m-%d`) to have its answer
+                        // compared semantically instead of verbatim. An `@` that
+                        // doesn't resolve to a known type is left as plain text.
+                        let (text, converter) = match data.rsplit_once('@') {
+                            Some((text, annotation)) => {
+                                match Converter::parse_annotation(annotation) {
+                                    Some(converter) => (text.trim().to_string(), Some(converter)),
+                                    None => (data.to_string(), None),
+                                }
                             }
+                            None => (data.to_string(), None),
+                        };
+                        lines.push(TypedLine { text, converter });
+                    }
+                    FillTheBlank(lines) => {
+                        // UPPERCASE indicates a blank
+                        // '<','>' alternative way to indicate a blank
+                        // '_' indicates that the following letter has to be entered
+                        //      as uppercase as well
+                        // '\' indicates that the following uppercase word is not
+                        // actually a blank
+                        let mut parts: LineWithBlanks = vec![];
+                        let mut index = 0;
+
+                        for part in data.split_whitespace() {
+                            //
+                            let blank =
+                                part.chars().all(|c| c.is_uppercase() || !c.is_alphanumeric());
+
+                            let p = if blank {
+                                String::from(part.to_lowercase())
+                            } else {
+                                String::from(part)
+                            };
+
+                            parts.push(LinePart(p, blank, index));
 
-                            lines.push(parts);
+                            index += part.len() + 1;
                         }
-                    },
-                    None => panic!("error parsing the file"),
+
+                        lines.push(parts);
+                    }
+                }
+
+                if !state.can_move_to(&ParserState::Back) {
+                    errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                    recover!();
+                    continue;
+                }
+                state = ParserState::Back;
+            }
+
+            TokenKind::Fence => {
+                if skipping {
+                    continue;
+                }
+                if !state.can_move_to(&ParserState::Fenced) {
+                    errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                    recover!();
+                    continue;
                 }
-                state.move_to(ParserState::Back);
+                state = ParserState::Fenced;
+            }
+
+            TokenKind::Invalid => {
+                errors.push(err(token.line, token.col, ParseErrorKind::UnexpectedMarker));
+                recover!();
             }
         }
     }
 
-    let back = std::mem::replace(&mut card_back, None).unwrap();
-    factory.set_back(back);
-    // Is there one last flashcard in the factory that can be built?
-    //println!("can be built = {}", factory.can_build());
-    if factory.can_build() {
+    if state == ParserState::Fenced {
+        errors.push(err(line_no, 1, ParseErrorKind::UnterminatedFence));
+    } else {
+        match std::mem::replace(&mut card_back, None) {
+            Some(back) => {
+                factory.set_back(back);
+                if factory.can_build() {
+                    flashcards.push(factory.build());
+                }
+            }
+            None if flashcards.is_empty() && errors.is_empty() => {
+                errors.push(err(0, 0, ParseErrorKind::UnterminatedCard));
+            }
+            None => (),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(flashcards)
+    } else {
+        Err(errors)
+    }
+}
+
+/// The on-disk format a deck is imported from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeckFormat
+{
+    /// This crate's own `MARKUP_FACE`/`MARKUP_NOTE` markup, read by `parse_bytes`.
+    Native,
+    /// Comma-separated values, one card per line: face, back (a single-line
+    /// `WriteTheLine` card), and an optional note.
+    Csv,
+    /// Tab-separated values, laid out the same as `Csv`.
+    TabSeparated,
+}
+
+impl DeckFormat
+{
+    /// Guesses the format from a file's extension, defaulting to `Native` when the
+    /// extension is missing or unrecognized.
+    pub fn from_path(path: &Path) -> Self
+    {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => DeckFormat::Csv,
+            Some("tsv") => DeckFormat::TabSeparated,
+            _ => DeckFormat::Native,
+        }
+    }
+}
+
+/// Imports a deck from `path`, guessing the `DeckFormat` from the file extension when
+/// `format` is `None`.
+///
+/// This lets users load decks exported from other tools (e.g. Anki's CSV export)
+/// without hand-converting them to the native markup first.
+pub fn import(path: &str, format: Option<DeckFormat>) -> Result<Vec<Flashcard>, ParseError>
+{
+    let format = format.unwrap_or_else(|| DeckFormat::from_path(Path::new(path)));
+
+    match format {
+        DeckFormat::Native => parse_from_file(path),
+        DeckFormat::Csv => import_delimited(path, ','),
+        DeckFormat::TabSeparated => import_delimited(path, '\t'),
+    }
+}
+
+/// Imports a `DeckFormat::Csv`/`DeckFormat::TabSeparated` deck, mapping column 0 to the
+/// face, column 1 to the back (a `WriteTheLine` card), and an optional column 2 to the
+/// note.
+///
+/// Rows are split on line breaks before any quoting is considered, so a quoted field
+/// cannot span several lines and every imported back holds exactly one line.
+fn import_delimited(path: &str, delimiter: char) -> Result<Vec<Flashcard>, ParseError>
+{
+    use crate::flashcards::FlashcardBack::WriteTheLine;
+
+    let path_ref = Path::new(path);
+    let io_err = |message: String| ParseError {
+        file: path_ref.display().to_string(),
+        line: 0,
+        col: 0,
+        kind: ParseErrorKind::Io(message),
+    };
+
+    let src = std::fs::read_to_string(path_ref).map_err(|e| io_err(e.to_string()))?;
+    let name = path_ref
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io_err(format!("{} has no valid file name", path_ref.display())))?;
+
+    let err = |line: usize, kind: ParseErrorKind| ParseError { file: name.to_string(), line, col: 0, kind };
+
+    let mut flashcards = vec![];
+    for (line_no, line) in src.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let columns = split_delimited_row(line, delimiter);
+        if columns.len() < 2 {
+            return Err(err(line_no + 1, ParseErrorKind::MissingFace));
+        }
+
+        let mut factory = FlashcardFactory::new(name);
+        factory.set_face(&columns[0]);
+        let back = columns[1]
+            .lines()
+            .map(|text| TypedLine { text: text.to_string(), converter: None })
+            .collect();
+        factory.set_back(WriteTheLine(back));
+        if let Some(note) = columns.get(2).filter(|s| !s.is_empty()) {
+            factory.set_note(note);
+        }
+
         flashcards.push(factory.build());
     }
 
-    flashcards
+    Ok(flashcards)
+}
+
+/// Splits a single CSV/TSV row on `delimiter`, honoring double-quoted fields (which may
+/// contain the delimiter) and the `MARKUP_ESCAPE` character the native parser uses.
+fn split_delimited_row(line: &str, delimiter: char) -> Vec<String>
+{
+    use crate::constants::MARKUP_ESCAPE;
+
+    let mut columns = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == MARKUP_ESCAPE => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                columns.push(current.clone());
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+    columns.push(current);
+
+    columns
+}
+
+/// Strips a single trailing `\r`, so CRLF-terminated decks parse the same as LF-only
+/// ones, matching the behavior `BufRead::lines()` gives the old reader-based `parse`.
+fn strip_trailing_cr(line: &[u8]) -> &[u8]
+{
+    match line.split_last() {
+        Some((b'\r', rest)) => rest,
+        _ => line,
+    }
+}
+
+/// Whether `raw_line` is a `MARKUP_FENCE` delimiter once trimmed, i.e. the line that
+/// closes a verbatim block. Unlike the lines the fence surrounds, the delimiter itself
+/// is always safe to trim and validate, since it's never treated as card content.
+fn is_fence_line(raw_line: &[u8]) -> bool
+{
+    match std::str::from_utf8(raw_line) {
+        Ok(line) => line.trim() == crate::constants::MARKUP_FENCE,
+        Err(_) => false,
+    }
 }
 
 #[cfg(test)]
@@ -239,7 +652,7 @@ mod tests
 
         ");
 
-        assert_eq!(1, parse(s, "test").len());
+        assert_eq!(1, parse(s, "test").unwrap().len());
     }
 
     #[test]
@@ -254,7 +667,7 @@ mod tests
             this is another write-the-line flashcard
         ");
 
-        assert_eq!(2, parse(s, "test").len());
+        assert_eq!(2, parse(s, "test").unwrap().len());
     }
 
     #[test]
@@ -266,7 +679,7 @@ mod tests
             this is a FILL_THE_BLANK flashcard
         ");
 
-        assert_eq!(1, parse(s, "test").len());
+        assert_eq!(1, parse(s, "test").unwrap().len());
     }
 
     #[test]
@@ -281,7 +694,7 @@ mod tests
             this is another FILL_THE_BLANK flashcard
         ");
 
-        assert_eq!(2, parse(s, "test").len());
+        assert_eq!(2, parse(s, "test").unwrap().len());
     }
 
     #[test]
@@ -291,13 +704,322 @@ mod tests
         let s = Cursor::new("
         # WriteTheLine
             this is a write-the-line flashcard
-        
+
         ## FillTheBlank
             this is a FILL_THE_BLANK flashcard
         ");
 
-        let flashcards = parse(s, "test");
+        let flashcards = parse(s, "test").unwrap();
         println!("{:?}", flashcards);
         assert_eq!(2, flashcards.len());
     }
+
+    #[test]
+    fn parse_write_the_line_with_a_type_annotation()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        # How many days in a week?
+            7@int
+        ");
+
+        let flashcards = parse(s, "test").unwrap();
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                assert_eq!("7", lines[0].text);
+                assert_eq!(Some(Converter::Int), lines[0].converter);
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn an_unresolvable_annotation_is_kept_as_plain_text()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        # Whose handle is this?
+            user@example.com
+        ");
+
+        let flashcards = parse(s, "test").unwrap();
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                assert_eq!("user@example.com", lines[0].text);
+                assert_eq!(None, lines[0].converter);
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_note_before_any_face_is_reported_and_skipped()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        ! a stray note
+        # WriteTheLine
+            this is a write-the-line flashcard
+        ");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::UnexpectedMarker, errors[0].kind);
+        assert_eq!(2, errors[0].line);
+    }
+
+    #[test]
+    fn a_face_with_no_front_text_is_reported_and_skipped()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        #
+        # WriteTheLine
+            this is a write-the-line flashcard
+        ");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::MissingFace, errors[0].kind);
+    }
+
+    #[test]
+    fn an_unsupported_card_type_is_reported_and_skipped()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        #### Too many hashes
+            this line never gets attached to a card
+        # WriteTheLine
+            this is a write-the-line flashcard
+        ");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::UnsupportedCardType, errors[0].kind);
+    }
+
+    #[test]
+    fn an_empty_deck_is_reported_as_unterminated()
+    {
+        let s = Cursor::new("% only a comment\n");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::UnterminatedCard, errors[0].kind);
+    }
+
+    #[test]
+    fn scanning_continues_after_an_error_so_later_mistakes_are_also_reported()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("
+        ! a stray note
+        # WriteTheLine
+            this is a write-the-line flashcard
+        #
+        ");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(2, errors.len());
+        assert_eq!(ParseErrorKind::UnexpectedMarker, errors[0].kind);
+        assert_eq!(ParseErrorKind::MissingFace, errors[1].kind);
+    }
+
+    #[test]
+    fn parse_bytes_accepts_crlf_line_endings()
+    {
+        let s = b"\r\n# WriteTheLine\r\n    this is a write-the-line flashcard\r\n";
+
+        assert_eq!(1, parse_bytes(s, "test").unwrap().len());
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported_and_scanning_recovers()
+    {
+        let mut s = b"\n# WriteTheLine\n    ".to_vec();
+        s.extend_from_slice(&[0xff, 0xfe]);
+        s.extend_from_slice(b"\n# WriteTheLine 2\n    this is another flashcard\n");
+
+        let errors = parse_bytes(&s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::InvalidUtf8, errors[0].kind);
+        assert_eq!(3, errors[0].line);
+    }
+
+    /// Writes `content` to a uniquely-named file under the OS temp dir and returns its
+    /// path, so tests exercising the file-based entry points don't collide when run
+    /// concurrently.
+    fn write_temp_deck(name: &str, content: &str) -> std::path::PathBuf
+    {
+        let path = std::env::temp_dir().join(format!(
+            "cardbox_parser_test_{}_{}.txt",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, content).expect("error writing test fixture");
+        path
+    }
+
+    #[test]
+    fn parse_from_files_concatenates_decks_tagging_each_by_its_own_subject()
+    {
+        let path_a = write_temp_deck("a", "\n# Capital of France\n    Paris\n");
+        let path_b = write_temp_deck("b", "\n# Capital of Germany\n    Berlin\n");
+
+        let paths = [path_a.to_str().unwrap(), path_b.to_str().unwrap()];
+        let flashcards = parse_from_files(&paths).unwrap();
+
+        assert_eq!(2, flashcards.len());
+        assert_ne!(flashcards[0].subject, flashcards[1].subject);
+
+        std::fs::remove_file(path_a).ok();
+        std::fs::remove_file(path_b).ok();
+    }
+
+    #[test]
+    fn parse_from_files_reports_which_file_and_line_failed()
+    {
+        let path_a = write_temp_deck("ok", "\n# Capital of France\n    Paris\n");
+        let path_b = write_temp_deck("bad", "\n! a stray note\n");
+
+        let paths = [path_a.to_str().unwrap(), path_b.to_str().unwrap()];
+        let error = parse_from_files(&paths).unwrap_err();
+
+        assert_eq!(ParseErrorKind::UnexpectedMarker, error.kind);
+        assert!(error.file.contains("bad"));
+
+        std::fs::remove_file(path_a).ok();
+        std::fs::remove_file(path_b).ok();
+    }
+
+    #[test]
+    fn parse_many_concatenates_readers_tagging_each_by_its_own_name()
+    {
+        let readers: Vec<Box<dyn BufRead>> = vec![
+            Box::new(Cursor::new("\n# Capital of France\n    Paris\n")),
+            Box::new(Cursor::new("\n# Capital of Germany\n    Berlin\n")),
+        ];
+        let names = ["geography-1", "geography-2"];
+
+        let flashcards = parse_many(readers, &names).unwrap();
+
+        assert_eq!(2, flashcards.len());
+        assert_ne!(flashcards[0].subject, flashcards[1].subject);
+    }
+
+    #[test]
+    fn a_fenced_block_preserves_indentation_and_blank_lines_verbatim()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new(
+            "\n# What does this print?\n    ~~~\n    fn main() {\n\n        println!(\"hi\");\n    }\n    ~~~\n"
+        );
+
+        let flashcards = parse(s, "test").unwrap();
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                let texts: Vec<&str> = lines.iter().map(|l| l.text.as_str()).collect();
+                assert_eq!(vec!["    fn main() {", "", "        println!(\"hi\");", "    }"], texts);
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_fenced_block_does_not_treat_a_hash_line_as_a_new_card()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new(
+            "\n# Show me the markup\n    ~~~\n    # not a new card\n    ~~~\n"
+        );
+
+        let flashcards = parse(s, "test").unwrap();
+        assert_eq!(1, flashcards.len());
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                assert_eq!(1, lines.len());
+                assert_eq!("    # not a new card", lines[0].text);
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn an_unclosed_fence_at_eof_is_reported_instead_of_silently_kept_verbatim()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("\n# Show me the markup\n    ~~~\n    never closed\n");
+
+        let errors = parse(s, "test").unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ParseErrorKind::UnterminatedFence, errors[0].kind);
+    }
+
+    #[test]
+    fn an_unopened_fence_close_outside_a_fence_is_just_a_fence_open()
+    {
+        #[rustfmt::skip]
+        let s = Cursor::new("\n# WriteTheLine\n    ~~~\n    verbatim line\n    ~~~\n    after fence\n");
+
+        let flashcards = parse(s, "test").unwrap();
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                let texts: Vec<&str> = lines.iter().map(|l| l.text.as_str()).collect();
+                assert_eq!(vec!["    verbatim line", "after fence"], texts);
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deck_format_from_path_detects_csv_and_tsv()
+    {
+        assert_eq!(DeckFormat::Csv, DeckFormat::from_path(Path::new("deck.csv")));
+        assert_eq!(DeckFormat::TabSeparated, DeckFormat::from_path(Path::new("deck.tsv")));
+        assert_eq!(DeckFormat::Native, DeckFormat::from_path(Path::new("deck.txt")));
+    }
+
+    #[test]
+    fn split_delimited_row_honors_quotes_and_escapes()
+    {
+        let columns = split_delimited_row(r#"hello,"a, b",\,not a delimiter"#, ',');
+        assert_eq!(vec!["hello", "a, b", ",not a delimiter"], columns);
+    }
+
+    #[test]
+    fn import_reads_a_csv_deck_into_write_the_line_cards()
+    {
+        let path = write_temp_deck("csv", "Capital of France,Paris\nCapital of Germany,\"Berlin\"\n");
+
+        let flashcards = import(path.to_str().unwrap(), Some(DeckFormat::Csv)).unwrap();
+
+        assert_eq!(2, flashcards.len());
+        assert_eq!("Capital of France", flashcards[0].face);
+        match &flashcards[0].back {
+            FlashcardBack::WriteTheLine(lines) => {
+                assert_eq!(vec!["Paris"], lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>());
+            }
+            other => panic!("expected WriteTheLine, got {:?}", other),
+        }
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn import_guesses_tab_separated_from_the_file_extension()
+    {
+        let path = std::env::temp_dir().join(format!(
+            "cardbox_parser_test_tsv_{}.tsv",
+            std::process::id()
+        ));
+        std::fs::write(&path, "Capital of France\tParis\n").expect("error writing test fixture");
+
+        let flashcards = import(path.to_str().unwrap(), None).unwrap();
+
+        assert_eq!(1, flashcards.len());
+        assert_eq!("Capital of France", flashcards[0].face);
+
+        std::fs::remove_file(path).ok();
+    }
 }
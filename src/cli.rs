@@ -11,6 +11,18 @@ struct Args
 {
     #[structopt(short, long, help = "file path to the cardbox")]
     filepath: String,
+
+    #[structopt(long, help = "encrypt the progress database with a passphrase")]
+    encrypt: Option<String>,
+
+    #[structopt(long, help = "encrypt the progress database with a key file", parse(from_os_str))]
+    key_file: Option<std::path::PathBuf>,
+
+    #[structopt(long, help = "use the SM-2 adaptive scheduler instead of the default Leitner boxes")]
+    sm2: bool,
+
+    #[structopt(long, help = "sync progress with a remote server at this base URL before and after the session")]
+    sync_url: Option<String>,
 }
 
 impl Cli
@@ -26,4 +38,34 @@ impl Cli
     {
         &self.args.filepath
     }
+
+    /// Returns whether `--sm2` was given, selecting the SM-2 adaptive scheduler over the
+    /// default Leitner `Cardbox`.
+    pub fn use_sm2(&self) -> bool
+    {
+        self.args.sm2
+    }
+
+    /// Returns the base URL to sync progress with, if `--sync-url` was given.
+    pub fn sync_url(&self) -> Option<&str>
+    {
+        self.args.sync_url.as_deref()
+    }
+
+    /// Returns the key source used to encrypt the progress database, if any.
+    ///
+    /// A `--key-file` takes precedence over `--encrypt`, since a file on disk is
+    /// generally considered the stronger key source. Returns `None` when neither was
+    /// given, in which case the database is kept in plaintext.
+    #[cfg(feature = "encryption")]
+    pub fn key_source(&self) -> Option<crate::db::crypto::KeySource>
+    {
+        use crate::db::crypto::KeySource;
+
+        if let Some(path) = &self.args.key_file {
+            Some(KeySource::KeyFile(path.clone()))
+        } else {
+            self.args.encrypt.clone().map(KeySource::Passphrase)
+        }
+    }
 }
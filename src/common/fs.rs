@@ -1,6 +1,6 @@
 //! Filesystem related common utility functions.
 
-use crate::constants::{APP_NAME, DB_NAME};
+use crate::constants::{APP_NAME, CARDBOX_CONFIG_NAME, DB_NAME};
 
 /// Return the application's directory for persistent data storage.
 pub fn get_app_persistence_path() -> String
@@ -17,3 +17,11 @@ pub fn get_progress_db_path() -> String
     let path = path.join(APP_NAME).join(DB_NAME);
     format!("{}", path.display())
 }
+
+/// Return the path of the cardbox config file (stage count, cooldowns, queue size).
+pub fn get_cardbox_config_path() -> String
+{
+    let path = dirs::home_dir().expect("error retreiving home directory");
+    let path = path.join(APP_NAME).join(CARDBOX_CONFIG_NAME);
+    format!("{}", path.display())
+}
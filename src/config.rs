@@ -0,0 +1,147 @@
+//! Loads the user-tunable shape of the `Cardbox` Leitner box (number of stages, their
+//! cooldowns, and the initial queue size) from a small TOML config file, so the box can
+//! be retuned without recompiling.
+
+use crate::constants::{
+    INITIAL_QUEUE_SIZE, STAGE1_COOLDOWN, STAGE2_COOLDOWN, STAGE3_COOLDOWN, STAGE4_COOLDOWN,
+    STAGE5_COOLDOWN,
+};
+
+use std::fs;
+use std::path::Path;
+
+/// The tunable shape of a `Cardbox`: how many stages it has, how long a flashcard waits
+/// in each one, and how many new flashcards may enter stage 1 at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardboxConfig
+{
+    /// Seconds a flashcard waits in each stage before it becomes due again, indexed
+    /// from stage 1 (stage 0 is the new-card pool and has no cooldown of its own).
+    pub stage_cooldowns_secs: Vec<u64>,
+    /// Number of flashcards that may be newly admitted into stage 1 at once.
+    pub initial_queue_size: usize,
+}
+
+impl Default for CardboxConfig
+{
+    /// Reproduces the built-in five-stage box this crate shipped with before stages
+    /// became configurable.
+    fn default() -> Self
+    {
+        Self {
+            stage_cooldowns_secs: vec![
+                STAGE1_COOLDOWN,
+                STAGE2_COOLDOWN,
+                STAGE3_COOLDOWN,
+                STAGE4_COOLDOWN,
+                STAGE5_COOLDOWN,
+            ],
+            initial_queue_size: INITIAL_QUEUE_SIZE,
+        }
+    }
+}
+
+impl CardboxConfig
+{
+    /// Loads a config from `path`, falling back to `CardboxConfig::default()` if the
+    /// file is missing or malformed.
+    pub fn load(path: impl AsRef<Path>) -> Self
+    {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| Self::parse(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Parses the flat `key = value` subset of TOML this config needs: a
+    /// `stage_cooldowns_secs` array of integers and an `initial_queue_size` integer, one
+    /// per line, `#` starting a comment.
+    fn parse(text: &str) -> Result<Self, String>
+    {
+        let mut config = Self::default();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key = value`, found `{}`", line))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "stage_cooldowns_secs" => {
+                    config.stage_cooldowns_secs = parse_u64_array(value)?;
+                }
+                "initial_queue_size" => {
+                    config.initial_queue_size =
+                        value.parse::<usize>().map_err(|e| e.to_string())?;
+                }
+                _ => return Err(format!("unknown config key `{}`", key)),
+            }
+        }
+
+        if config.stage_cooldowns_secs.is_empty() {
+            return Err("stage_cooldowns_secs must not be empty".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parses a TOML-style `[1, 2, 3]` array of unsigned integers.
+fn parse_u64_array(value: &str) -> Result<Vec<u64>, String>
+{
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| format!("expected `[..]`, found `{}`", value))?;
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u64>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parses_a_custom_config()
+    {
+        let text = "initial_queue_size = 5\nstage_cooldowns_secs = [0, 60, 3600]\n";
+        let config = CardboxConfig::parse(text).expect("error parsing config");
+
+        assert_eq!(5, config.initial_queue_size);
+        assert_eq!(vec![0, 60, 3600], config.stage_cooldowns_secs);
+    }
+
+    #[test]
+    fn ignores_comments_and_blank_lines()
+    {
+        let text = "# a 3-stage fast box\ninitial_queue_size = 3\n\nstage_cooldowns_secs = [0, 120, 240] # minutes in debug builds\n";
+        let config = CardboxConfig::parse(text).expect("error parsing config");
+
+        assert_eq!(vec![0, 120, 240], config.stage_cooldowns_secs);
+    }
+
+    #[test]
+    fn rejects_an_empty_stage_list()
+    {
+        let text = "stage_cooldowns_secs = []\n";
+        assert!(CardboxConfig::parse(text).is_err());
+    }
+
+    #[test]
+    fn load_falls_back_to_the_default_when_the_file_is_missing()
+    {
+        let config = CardboxConfig::load("./does_not_exist.toml");
+        assert_eq!(CardboxConfig::default(), config);
+    }
+}
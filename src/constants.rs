@@ -5,6 +5,9 @@ pub const APP_VERSION: &str = "0.1.0";
 
 pub const DB_NAME: &str = "progress.db";
 
+/// Name of the user-editable config file for the cardbox's stage layout.
+pub const CARDBOX_CONFIG_NAME: &str = "cardbox.toml";
+
 pub const HEADER_HEIGHT: u16 = 2;
 
 /// Markup indicating the front side of a flashcard.
@@ -23,6 +26,11 @@ pub const MARKUP_ESCAPE: char = '\\';
 /// Markup characters
 pub const MARKUP: [char; 3] = [MARKUP_FACE, MARKUP_COMMENT, MARKUP_NOTE];
 
+/// Markup fencing off a verbatim block: a line consisting of exactly this, on its own,
+/// opens or closes a region whose lines are kept exactly as written, with no trimming,
+/// no markup classification, and no escape processing.
+pub const MARKUP_FENCE: &str = "~~~";
+
 /// The prompt whenever user input is required.
 pub const PROMPT_INPUT: char = '>';
 pub const PROMPT_WIDTH: u16 = 1;
@@ -71,3 +79,13 @@ pub const TYPE_WRITE_THE_LINE: usize = 1;
 pub const TYPE_FILL_THE_BLANK: usize = 2;
 
 pub const BLANK_INDICATOR: char = '_';
+
+/// How often the background event thread wakes up to check on pending input, in
+/// milliseconds. Small enough that a `Tick` never lags noticeably behind a keystroke.
+pub const POLL_RATE_MS: u64 = 5;
+
+/// How often a `Tick` event is forwarded to the input loops, in milliseconds.
+pub const TICK_RATE_MS: u64 = 250;
+
+/// Number of seconds a flashcard may be worked on before it is automatically failed.
+pub const CARD_TIME_LIMIT_SECS: u64 = 30;
@@ -1,71 +1,724 @@
 //! A simple key-value store for storing flashcard progress.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 
 /// Represents a stage that a flashcard can be in.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Stage {
     pub index: u64,
     pub timestamp_ms: u64,
+    /// SM-2 easiness factor: how quickly the interval grows after a correct recall.
+    pub ef: f32,
+    /// SM-2 interval until the card is next due, in days.
+    pub interval_days: u32,
+    /// Number of consecutive successful reviews.
+    pub reps: u32,
 }
 
-/// Loads the DB into a hashmap of flashcard hashes, and their respective stages and
-/// timestamps when they entered a certain stage.
-pub fn load(fname: &str) -> HashMap<u64, Stage> {
-    let path = if cfg!(debug_assertions) {
-        Path::new("./sample_db.txt")
+impl Default for Stage {
+    fn default() -> Self {
+        Self { index: 0, timestamp_ms: 0, ef: 2.5, interval_days: 0, reps: 0 }
+    }
+}
+
+/// Applies one SM-2 review step to `stage`.
+///
+/// `quality` is the user's recall grade from 0 (complete blackout) to 5 (perfect
+/// recall). A `quality` of 3 or higher is treated as a correct recall and grows the
+/// interval; anything lower is a lapse that resets it. `stage.timestamp_ms` becomes the
+/// timestamp at which the card is next due.
+pub fn review(stage: &mut Stage, quality: u8, now_ms: u64) {
+    if quality >= 3 {
+        stage.interval_days = match stage.reps {
+            0 => 1,
+            1 => 6,
+            _ => (stage.interval_days as f32 * stage.ef).round() as u32,
+        };
+        stage.reps += 1;
     } else {
-        Path::new(fname)
-    };
-    let file = if path.exists() {
-        File::open(&path).expect("error opening progress database")
+        stage.reps = 0;
+        stage.interval_days = 1;
+    }
+
+    let quality = f32::from(quality);
+    stage.ef =
+        (stage.ef + (0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02))).max(1.3);
+
+    stage.timestamp_ms = now_ms + u64::from(stage.interval_days) * 86_400_000;
+}
+
+/// Returns the hashes of the flashcards that are due for review at `now_ms`.
+pub fn due_cards(db: &HashMap<u64, Stage>, now_ms: u64) -> Vec<u64> {
+    db.iter()
+        .filter(|(_, stage)| stage.timestamp_ms <= now_ms)
+        .map(|(hash, _)| *hash)
+        .collect()
+}
+
+/// Errors that can occur while reading or writing the progress database.
+#[derive(Debug)]
+pub enum Error {
+    /// The backend failed to read its underlying storage.
+    Read(String),
+    /// The backend failed to write its underlying storage.
+    Write(String),
+    /// A stored record could not be decoded.
+    Decode(String),
+    /// A record could not be encoded for storage.
+    Encode(String),
+    /// The database was written by a newer, unrecognized schema version.
+    UnsupportedVersion(u16),
+    /// The database was written by a newer, unrecognized text encoding version.
+    UnsupportedFormat(u16),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read(msg) => write!(f, "error reading progress database: {}", msg),
+            Error::Write(msg) => write!(f, "error writing progress database: {}", msg),
+            Error::Decode(msg) => write!(f, "error decoding progress record: {}", msg),
+            Error::Encode(msg) => write!(f, "error encoding progress record: {}", msg),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "progress database schema version {} is newer than this build understands",
+                version
+            ),
+            Error::UnsupportedFormat(version) => write!(
+                f,
+                "progress database format version {} is newer than this build understands",
+                version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Abstracts over where the raw bytes of the progress database live, so the
+/// encoding/decoding logic doesn't need to know whether it's talking to a file, an
+/// in-memory buffer (for tests), or something else entirely.
+pub trait StoreBackend {
+    /// Reads the entire contents of the backing store.
+    fn read(&self) -> Result<Vec<u8>, Error>;
+
+    /// Overwrites the entire contents of the backing store.
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error>;
+}
+
+/// A `StoreBackend` that persists to a single file on disk.
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    /// Creates a new file-backed store rooted at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+}
+
+impl StoreBackend for FileBackend {
+    fn read(&self) -> Result<Vec<u8>, Error> {
+        if !self.path.exists() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .create(crate::common::fs::get_app_persistence_path())
+                .map_err(|e| Error::Read(e.to_string()))?;
+
+            File::create(&self.path).map_err(|e| Error::Write(e.to_string()))?;
+        }
+
+        std::fs::read(&self.path).map_err(|e| Error::Read(e.to_string()))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        std::fs::write(&self.path, bytes).map_err(|e| Error::Write(e.to_string()))
+    }
+}
+
+/// Reads and writes the progress database as whole snapshots, so `Cardbox` and
+/// `Sm2Scheduler` don't need to know whether progress lives in a file, in memory, or on
+/// a remote server.
+pub trait ProgressStore {
+    /// Loads the entire progress database.
+    fn load(&self) -> Result<HashMap<u64, Stage>, Error>;
+
+    /// Overwrites the entire progress database.
+    fn save(&self, progress: &HashMap<u64, Stage>) -> Result<(), Error>;
+}
+
+/// The async counterpart of `ProgressStore`, for backends whose I/O is naturally
+/// non-blocking, such as a networked store or an async database driver.
+pub trait AsyncProgressStore {
+    /// Loads the entire progress database.
+    fn load_async(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<HashMap<u64, Stage>, Error>> + Send>>;
+
+    /// Overwrites the entire progress database.
+    fn save_async(
+        &self,
+        progress: HashMap<u64, Stage>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+}
+
+/// Adapts any `AsyncProgressStore` into a `ProgressStore`, for today's synchronous
+/// `main.rs` loop, by polling the returned future to completion on the current thread.
+pub struct Blocking<A>(pub A);
+
+impl<A: AsyncProgressStore> ProgressStore for Blocking<A> {
+    fn load(&self) -> Result<HashMap<u64, Stage>, Error> {
+        block_on(self.0.load_async())
+    }
+
+    fn save(&self, progress: &HashMap<u64, Stage>) -> Result<(), Error> {
+        block_on(self.0.save_async(progress.clone()))
+    }
+}
+
+/// Polls `future` to completion on the current thread, yielding between polls.
+///
+/// This is a minimal stand-in for a full async runtime, only good enough for backends
+/// whose future resolves after a handful of polls; it is not meant to replace
+/// `tokio`/`async-std` for anything that does real asynchronous waiting.
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    /// A waker that does nothing: `block_on` already re-polls in a tight loop, so it
+    /// never needs waking up.
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Arc::new(NoopWaker).into();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::yield_now(),
+        }
+    }
+}
+
+/// The default `ProgressStore`: persists the whole database to a single file on disk.
+pub struct FileProgressStore {
+    fname: String,
+    #[cfg(feature = "encryption")]
+    key_source: Option<crypto::KeySource>,
+}
+
+impl FileProgressStore {
+    /// Creates a store rooted at `fname`.
+    pub fn new(fname: impl Into<String>) -> Self {
+        Self {
+            fname: fname.into(),
+            #[cfg(feature = "encryption")]
+            key_source: None,
+        }
+    }
+
+    /// Encrypts the store at rest with `key_source`, or leaves it in plaintext when
+    /// `None`.
+    #[cfg(feature = "encryption")]
+    pub fn with_key(mut self, key_source: Option<crypto::KeySource>) -> Self {
+        self.key_source = key_source;
+        self
+    }
+}
+
+impl Default for FileProgressStore {
+    /// Creates a store rooted at the user's default progress database path.
+    fn default() -> Self {
+        Self::new(crate::common::fs::get_progress_db_path())
+    }
+}
+
+impl ProgressStore for FileProgressStore {
+    fn load(&self) -> Result<HashMap<u64, Stage>, Error> {
+        #[cfg(feature = "encryption")]
+        {
+            load_with_key(&self.fname, self.key_source.clone())
+        }
+        #[cfg(not(feature = "encryption"))]
+        {
+            load(&self.fname)
+        }
+    }
+
+    /// Appends only the records that actually changed since the last load/save, rather
+    /// than rewriting the whole file, compacting it once `needs_compaction` says it has
+    /// accumulated enough stale records to be worth the rewrite.
+    ///
+    /// Callers (`Cardbox::save`, `Sm2Scheduler::save`) always pass the *entire* progress
+    /// map, so this diffs it against what's currently on disk to find the subset worth
+    /// appending; otherwise a save would re-append every card on every study session,
+    /// regardless of how many actually changed, defeating the whole point of an
+    /// append-only log.
+    ///
+    /// When encryption is configured this instead falls back to `save_with_key`'s full
+    /// rewrite: `append`/`compact` write straight to disk through `resolve_path`,
+    /// bypassing `EncryptingBackend`, so appending here would leak plaintext records
+    /// into what's supposed to be an encrypted file.
+    fn save(&self, progress: &HashMap<u64, Stage>) -> Result<(), Error> {
+        #[cfg(feature = "encryption")]
+        {
+            if self.key_source.is_some() {
+                return save_with_key(progress, &self.fname, self.key_source.clone());
+            }
+        }
+
+        let previous = load(&self.fname)?;
+
+        for (hash, stage) in progress {
+            if previous.get(hash) != Some(stage) {
+                append(&self.fname, *hash, stage)?;
+            }
+        }
+
+        if needs_compaction(&self.fname)? {
+            compact(&self.fname, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the on-disk path for a progress database, honoring the debug override that
+/// keeps development runs off the user's real database: in debug builds that path is
+/// redirected to `./sample_db.txt`, while any other path is used as given.
+fn resolve_path(fname: &str) -> PathBuf {
+    if cfg!(debug_assertions) && fname == crate::common::fs::get_progress_db_path() {
+        PathBuf::from("./sample_db.txt")
     } else {
-        std::fs::DirBuilder::new()
-            .recursive(true)
-            .create(crate::common::fs::get_app_persistence_path())
-            .expect("error creating db directory");
+        PathBuf::from(fname)
+    }
+}
+
+/// Identifies a file as one of this app's progress databases, so a foreign file is
+/// rejected with a clear `Decode` error instead of being misparsed.
+const APP_ID: &str = "flashdb";
+
+/// The on-disk text encoding's own version: the delimiter, field order and header
+/// layout `encode_text`/`decode_text` agree on.
+///
+/// Bump this when the *encoding itself* changes shape (e.g. switching delimiters, or
+/// moving from semicolon-separated fields to something else) independently of what
+/// fields a `Stage` record carries; that's tracked by `CURRENT_SCHEMA_VERSION` instead.
+const CURRENT_FORMAT_VERSION: u16 = 1;
+
+/// The current `Stage` schema version.
+///
+/// Bump this whenever the set of fields a record carries changes (a field added,
+/// removed, or reordered) and add a migration to `MIGRATIONS` so databases written by
+/// older builds keep loading.
+const CURRENT_SCHEMA_VERSION: u16 = 2;
+
+/// A behavior of the progress database schema that callers may want to gate on,
+/// mirroring `NetworkVersion::supports_*` predicates used for protocol negotiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Every `Stage` carries its SM-2 fields (`ef`, `interval_days`, `reps`), rather
+    /// than just `index`/`timestamp_ms`.
+    Sm2Fields,
+}
+
+/// Returns whether a database at `schema_version` supports `feature`.
+pub fn supports(schema_version: u16, feature: Feature) -> bool {
+    match feature {
+        Feature::Sm2Fields => schema_version >= 2,
+    }
+}
 
-        File::create(&path).expect("error creating progress database")
+/// One step in upgrading a progress database from the schema version immediately below
+/// `to`.
+struct Migration {
+    /// The schema version this migration upgrades a database to.
+    to: u16,
+    /// Rewrites `db` in place, assuming it is still shaped like `to - 1`.
+    apply: fn(&mut HashMap<u64, Stage>),
+}
+
+/// Registered migrations, applied in ascending order of `to`.
+const MIGRATIONS: &[Migration] = &[Migration { to: 2, apply: migrate_to_sm2_fields }];
+
+/// Schema version 1 databases only ever wrote `index`/`timestamp_ms`; `decode_text`
+/// already backfills the version-2 SM-2 fields with `Stage::default()`'s placeholders
+/// while parsing a version-1 record, so there is nothing left to rewrite here. The step
+/// still exists so the migration is explicit and later schema changes have a template.
+fn migrate_to_sm2_fields(_db: &mut HashMap<u64, Stage>) {}
+
+/// Runs every migration needed to bring `db` from `from_schema_version` up to
+/// `CURRENT_SCHEMA_VERSION`.
+fn migrate(db: &mut HashMap<u64, Stage>, from_schema_version: u16) {
+    for migration in MIGRATIONS {
+        if migration.to > from_schema_version {
+            (migration.apply)(db);
+        }
+    }
+}
+
+/// Encodes the progress database as a `{app_id};{format_version};{schema_version}`
+/// header followed by `hash;index;timestamp;ef;interval_days;reps` lines.
+pub(crate) fn encode_text(db: &HashMap<u64, Stage>) -> Vec<u8> {
+    let mut out =
+        format!("{};{};{}\n", APP_ID, CURRENT_FORMAT_VERSION, CURRENT_SCHEMA_VERSION);
+    for (hash, stage) in db.iter() {
+        out.push_str(&format!(
+            "{};{};{};{};{};{}\n",
+            hash, stage.index, stage.timestamp_ms, stage.ef, stage.interval_days, stage.reps
+        ));
+    }
+    out.into_bytes()
+}
+
+/// Decodes a header followed by record lines, migrating older schema versions up to
+/// `CURRENT_SCHEMA_VERSION` and rejecting a file from a newer format or schema version
+/// instead of misreading it.
+///
+/// A database with no header at all predates versioning. `append` also writes headerless
+/// lines (one record at a time, so there is nowhere to put a header), so such files are
+/// not necessarily schema version 1: the field count of the first record distinguishes a
+/// genuine pre-SM-2 database (3 fields) from a headerless-but-current one (6 fields).
+pub(crate) fn decode_text(bytes: &[u8]) -> Result<HashMap<u64, Stage>, Error> {
+    let text = String::from_utf8(bytes.to_vec()).map_err(|e| Error::Decode(e.to_string()))?;
+
+    let (format_version, schema_version, body) = match text.split_once('\n') {
+        Some((header, rest)) if header.starts_with(&format!("{};", APP_ID)) => {
+            let mut parts = header.splitn(3, ';');
+            parts.next(); // app id, already matched above
+
+            let format_version = parts
+                .next()
+                .ok_or_else(|| Error::Decode(String::from("missing format version")))?
+                .parse::<u16>()
+                .map_err(|e| Error::Decode(e.to_string()))?;
+            let schema_version = parts
+                .next()
+                .ok_or_else(|| Error::Decode(String::from("missing schema version")))?
+                .parse::<u16>()
+                .map_err(|e| Error::Decode(e.to_string()))?;
+
+            (format_version, schema_version, rest)
+        }
+        _ => {
+            let assumed_schema_version = text
+                .lines()
+                .find(|line| !line.is_empty())
+                .map(|line| if line.split(';').count() >= 6 { 2 } else { 1 })
+                .unwrap_or(CURRENT_SCHEMA_VERSION);
+            (CURRENT_FORMAT_VERSION, assumed_schema_version, text.as_str())
+        }
     };
-    let buffered = BufReader::new(file);
+
+    if format_version > CURRENT_FORMAT_VERSION {
+        return Err(Error::UnsupportedFormat(format_version));
+    }
+    if schema_version > CURRENT_SCHEMA_VERSION {
+        return Err(Error::UnsupportedVersion(schema_version));
+    }
 
     let mut result = HashMap::new();
 
-    buffered.lines().filter_map(|r| r.ok()).for_each(|line| {
+    for line in body.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
         let parts = line.split(';').collect::<Vec<&str>>();
+        let expected_fields = if supports(schema_version, Feature::Sm2Fields) { 6 } else { 3 };
+        if parts.len() != expected_fields {
+            return Err(Error::Decode(format!(
+                "expected {} fields, found {}",
+                expected_fields,
+                parts.len()
+            )));
+        }
 
-        let hash = parts[0].parse::<u64>().expect("error parsing hash");
-        let index = parts[1].parse::<u64>().expect("error parsing stage");
-        let timestamp_ms = parts[2].parse::<u64>().expect("error parsing unix timestamp");
+        let hash = parts[0].parse::<u64>().map_err(|e| Error::Decode(e.to_string()))?;
+        let index = parts[1].parse::<u64>().map_err(|e| Error::Decode(e.to_string()))?;
+        let timestamp_ms =
+            parts[2].parse::<u64>().map_err(|e| Error::Decode(e.to_string()))?;
 
-        //let hash = Hash(hash);
-        let stage = Stage { index, timestamp_ms };
+        let (ef, interval_days, reps) = if supports(schema_version, Feature::Sm2Fields) {
+            (
+                parts[3].parse::<f32>().map_err(|e| Error::Decode(e.to_string()))?,
+                parts[4].parse::<u32>().map_err(|e| Error::Decode(e.to_string()))?,
+                parts[5].parse::<u32>().map_err(|e| Error::Decode(e.to_string()))?,
+            )
+        } else {
+            let default = Stage::default();
+            (default.ef, default.interval_days, default.reps)
+        };
 
-        result.insert(hash, stage);
-    });
+        result.insert(hash, Stage { index, timestamp_ms, ef, interval_days, reps });
+    }
 
-    result
+    migrate(&mut result, schema_version);
+    Ok(result)
 }
 
-/// Saves the progress DB as a file.
-pub fn save(db: &HashMap<u64, Stage>, fname: &str) -> std::io::Result<()> {
-    let path = if cfg!(debug_assertions) {
-        Path::new("./sample_db.txt")
-    } else {
-        Path::new(fname)
-    };
-    let file = File::create(&path).expect("error creating db");
-    let mut buffered = BufWriter::new(file);
+/// A compact binary encoding built from postcard-style varint records, gated behind the
+/// `binary-values` feature so the text format remains the default until it has proven
+/// itself.
+#[cfg(feature = "binary-values")]
+mod binary {
+    use super::*;
 
-    for (hash, stage) in db.iter() {
-        writeln!(buffered, "{};{};{}", hash, stage.index, stage.timestamp_ms)?;
+    fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+        loop {
+            let mut byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value != 0 {
+                byte |= 0x80;
+            }
+            out.push(byte);
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, Error> {
+        let mut result = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = *bytes
+                .get(*pos)
+                .ok_or_else(|| Error::Decode(String::from("truncated varint")))?;
+            *pos += 1;
+
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        Ok(result)
     }
 
-    Ok(())
+    pub fn encode(db: &HashMap<u64, Stage>) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_varint(&mut out, db.len() as u64);
+
+        for (hash, stage) in db.iter() {
+            write_varint(&mut out, *hash);
+            write_varint(&mut out, stage.index);
+            write_varint(&mut out, stage.timestamp_ms);
+            write_varint(&mut out, stage.ef.to_bits() as u64);
+            write_varint(&mut out, u64::from(stage.interval_days));
+            write_varint(&mut out, u64::from(stage.reps));
+        }
+
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<HashMap<u64, Stage>, Error> {
+        let mut pos = 0;
+        let count = read_varint(bytes, &mut pos)?;
+        let mut result = HashMap::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let hash = read_varint(bytes, &mut pos)?;
+            let index = read_varint(bytes, &mut pos)?;
+            let timestamp_ms = read_varint(bytes, &mut pos)?;
+            let ef = f32::from_bits(read_varint(bytes, &mut pos)? as u32);
+            let interval_days = read_varint(bytes, &mut pos)? as u32;
+            let reps = read_varint(bytes, &mut pos)? as u32;
+            result.insert(hash, Stage { index, timestamp_ms, ef, interval_days, reps });
+        }
+
+        Ok(result)
+    }
+}
+
+/// Optional at-rest encryption of the progress database, so a database stored in a
+/// synced folder isn't readable without the key.
+#[cfg(feature = "encryption")]
+pub mod crypto {
+    use super::{Error, StoreBackend};
+
+    use chacha20::cipher::{KeyIvInit, StreamCipher};
+    use chacha20::ChaCha20;
+    use rand::RngCore;
+    use sha2::{Digest, Sha256};
+
+    use std::path::PathBuf;
+
+    const MAGIC: &[u8] = b"FCDB1";
+    const NONCE_LEN: usize = 12;
+
+    /// Where the encryption key is sourced from.
+    #[derive(Clone)]
+    pub enum KeySource {
+        /// A user-supplied passphrase, hashed down to a 256-bit key.
+        Passphrase(String),
+        /// A file whose contents are hashed down to a 256-bit key.
+        KeyFile(PathBuf),
+    }
+
+    fn derive_key(source: &KeySource) -> Result<[u8; 32], Error> {
+        let material = match source {
+            KeySource::Passphrase(pass) => pass.as_bytes().to_vec(),
+            KeySource::KeyFile(path) => {
+                std::fs::read(path).map_err(|e| Error::Read(e.to_string()))?
+            }
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(&material);
+        Ok(hasher.finalize().into())
+    }
+
+    /// Wraps a `StoreBackend` with a ChaCha20 stream cipher, keyed by an optional
+    /// `KeySource`. With no key configured it behaves exactly like the inner backend,
+    /// so existing plaintext databases keep working.
+    pub struct EncryptingBackend<B> {
+        inner: B,
+        key_source: Option<KeySource>,
+    }
+
+    impl<B: StoreBackend> EncryptingBackend<B> {
+        /// Wraps `inner`, encrypting with `key_source` when present.
+        pub fn new(inner: B, key_source: Option<KeySource>) -> Self {
+            Self { inner, key_source }
+        }
+    }
+
+    impl<B: StoreBackend> StoreBackend for EncryptingBackend<B> {
+        fn read(&self) -> Result<Vec<u8>, Error> {
+            let bytes = self.inner.read()?;
+
+            let key_source = match &self.key_source {
+                Some(source) => source,
+                None => return Ok(bytes),
+            };
+
+            if !bytes.starts_with(MAGIC) {
+                // An existing plaintext database: keep it readable even once
+                // encryption is turned on, rather than refusing to load it.
+                return Ok(bytes);
+            }
+
+            let rest = &bytes[MAGIC.len()..];
+            let newline = rest
+                .iter()
+                .position(|&b| b == b'\n')
+                .ok_or_else(|| Error::Decode(String::from("missing nonce header")))?;
+
+            let nonce = base64::decode(&rest[..newline])
+                .map_err(|e| Error::Decode(e.to_string()))?;
+            if nonce.len() != NONCE_LEN {
+                return Err(Error::Decode(String::from("invalid nonce length")));
+            }
+
+            let mut body = rest[newline + 1..].to_vec();
+            let key = derive_key(key_source)?;
+            let mut cipher = ChaCha20::new(&key.into(), nonce.as_slice().into());
+            cipher.apply_keystream(&mut body);
+
+            Ok(body)
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+            let key_source = match &self.key_source {
+                Some(source) => source,
+                None => return self.inner.write(bytes),
+            };
+
+            let mut nonce = [0u8; NONCE_LEN];
+            rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+            let key = derive_key(key_source)?;
+            let mut body = bytes.to_vec();
+            let mut cipher = ChaCha20::new(&key.into(), (&nonce).into());
+            cipher.apply_keystream(&mut body);
+
+            let mut out = Vec::with_capacity(MAGIC.len() + 1 + body.len());
+            out.extend_from_slice(MAGIC);
+            out.extend_from_slice(base64::encode(nonce).as_bytes());
+            out.push(b'\n');
+            out.extend_from_slice(&body);
+
+            self.inner.write(&out)
+        }
+    }
+}
+
+/// Loads the progress database through the given backend.
+pub fn load_from(backend: &mut dyn StoreBackend) -> Result<HashMap<u64, Stage>, Error> {
+    let bytes = backend.read()?;
+    if bytes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    #[cfg(feature = "binary-values")]
+    {
+        binary::decode(&bytes)
+    }
+    #[cfg(not(feature = "binary-values"))]
+    {
+        decode_text(&bytes)
+    }
+}
+
+/// Saves the progress database through the given backend.
+pub fn save_to(db: &HashMap<u64, Stage>, backend: &mut dyn StoreBackend) -> Result<(), Error> {
+    #[cfg(feature = "binary-values")]
+    let bytes = binary::encode(db);
+    #[cfg(not(feature = "binary-values"))]
+    let bytes = encode_text(db);
+
+    backend.write(&bytes)
+}
+
+/// Loads the DB into a hashmap of flashcard hashes, and their respective stages and
+/// timestamps when they entered a certain stage.
+pub fn load(fname: &str) -> Result<HashMap<u64, Stage>, Error> {
+    let mut backend = FileBackend::new(resolve_path(fname));
+    load_from(&mut backend)
+}
+
+/// Saves the progress DB as a file.
+pub fn save(db: &HashMap<u64, Stage>, fname: &str) -> Result<(), Error> {
+    let mut backend = FileBackend::new(resolve_path(fname));
+    save_to(db, &mut backend)
+}
+
+/// Loads the progress DB as a file, decrypting it with `key_source` when given.
+#[cfg(feature = "encryption")]
+pub fn load_with_key(
+    fname: &str,
+    key_source: Option<crypto::KeySource>,
+) -> Result<HashMap<u64, Stage>, Error> {
+    let file = FileBackend::new(resolve_path(fname));
+    let mut backend = crypto::EncryptingBackend::new(file, key_source);
+    load_from(&mut backend)
+}
+
+/// Saves the progress DB as a file, encrypting it with `key_source` when given.
+#[cfg(feature = "encryption")]
+pub fn save_with_key(
+    db: &HashMap<u64, Stage>,
+    fname: &str,
+    key_source: Option<crypto::KeySource>,
+) -> Result<(), Error> {
+    let file = FileBackend::new(resolve_path(fname));
+    let mut backend = crypto::EncryptingBackend::new(file, key_source);
+    save_to(db, &mut backend)
 }
 
 /// Cleans the database by removing all entries that are older then a particular unix
@@ -73,8 +726,8 @@ pub fn save(db: &HashMap<u64, Stage>, fname: &str) -> std::io::Result<()> {
 ///
 /// This function is useful to remove dead flashcards (are created when removed from a
 /// cardbox or after hash changing modifactions happend)
-pub fn clean(fname: &str, older_than: u64) {
-    let mut db = load(fname);
+pub fn clean(fname: &str, older_than: u64) -> Result<(), Error> {
+    let mut db = load(fname)?;
     let mut new_db = HashMap::new();
 
     for (hash, stage) in db.drain() {
@@ -83,7 +736,77 @@ pub fn clean(fname: &str, older_than: u64) {
         }
     }
 
-    save(&new_db, fname).expect("error saving progress database");
+    save(&new_db, fname)
+}
+
+/// Number of stale (superseded) records the log is allowed to accumulate before
+/// `needs_compaction` recommends rewriting it.
+const COMPACTION_THRESHOLD: usize = 64;
+
+/// Appends a single stage update to the end of the progress log instead of rewriting
+/// the whole database, which is O(1) per update rather than O(total cards) and can't
+/// be torn into a half-written file the way a full rewrite can.
+///
+/// `load` already replays the log keeping the last record per hash, since later
+/// insertions into the resulting `HashMap` simply overwrite earlier ones.
+pub fn append(fname: &str, hash: u64, stage: &Stage) -> Result<(), Error> {
+    let path = resolve_path(fname);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| Error::Write(e.to_string()))?;
+
+    let line = format!(
+        "{};{};{};{};{};{}\n",
+        hash, stage.index, stage.timestamp_ms, stage.ef, stage.interval_days, stage.reps
+    );
+
+    file.write_all(line.as_bytes()).map_err(|e| Error::Write(e.to_string()))
+}
+
+/// Counts the records in the log at `fname`, including stale ones superseded by a
+/// later append for the same hash.
+fn count_log_records(fname: &str) -> Result<usize, Error> {
+    let path = resolve_path(fname);
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let bytes = std::fs::read(&path).map_err(|e| Error::Read(e.to_string()))?;
+    let text = String::from_utf8(bytes).map_err(|e| Error::Decode(e.to_string()))?;
+
+    Ok(text.lines().filter(|line| !line.is_empty()).count())
+}
+
+/// Returns `true` once the log has accumulated more than `COMPACTION_THRESHOLD` stale
+/// records, so a caller can trigger `compact` lazily instead of on every save.
+pub fn needs_compaction(fname: &str) -> Result<bool, Error> {
+    let db = load(fname)?;
+    let total = count_log_records(fname)?;
+
+    Ok(total.saturating_sub(db.len()) > COMPACTION_THRESHOLD)
+}
+
+/// Rewrites the log so it contains only the surviving latest record per hash, folding
+/// in the same age cutoff as `clean`, and atomically renames the result into place so a
+/// crash mid-compaction can't leave a torn file behind.
+pub fn compact(fname: &str, older_than: u64) -> Result<(), Error> {
+    let mut db = load(fname)?;
+    let mut kept = HashMap::new();
+
+    for (hash, stage) in db.drain() {
+        if stage.timestamp_ms >= older_than {
+            kept.insert(hash, stage);
+        }
+    }
+
+    let path = resolve_path(fname);
+    let tmp_path = path.with_extension("tmp");
+
+    save_to(&kept, &mut FileBackend::new(&tmp_path))?;
+
+    std::fs::rename(&tmp_path, &path).map_err(|e| Error::Write(e.to_string()))
 }
 
 #[cfg(test)]
@@ -91,17 +814,149 @@ mod tests {
     use super::super::common::time;
     use super::*;
 
+    struct MemoryBackend {
+        bytes: Vec<u8>,
+    }
+
+    impl StoreBackend for MemoryBackend {
+        fn read(&self) -> Result<Vec<u8>, Error> {
+            Ok(self.bytes.clone())
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+            self.bytes = bytes.to_vec();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn save_and_load_roundtrip_through_a_backend() {
+        let mut db = HashMap::new();
+        let stage = Stage {
+            index: 3,
+            timestamp_ms: time::get_unix_time_millis(),
+            ..Stage::default()
+        };
+        db.insert(42, stage);
+
+        let mut backend = MemoryBackend { bytes: vec![] };
+        save_to(&db, &mut backend).expect("error saving progress database");
+
+        let loaded = load_from(&mut backend).expect("error loading progress database");
+        assert_eq!(1, loaded.len());
+        assert_eq!(3, loaded[&42].index);
+    }
+
+    #[test]
+    fn decode_text_rejects_a_malformed_record() {
+        let result = decode_text(b"42;3\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn decode_text_backfills_sm2_fields_for_a_schema_version_1_record() {
+        let db = decode_text(
+            format!("{};{};1\n42;3;1000\n", APP_ID, CURRENT_FORMAT_VERSION).as_bytes(),
+        )
+        .expect("error decoding a schema-version-1 database");
+
+        let stage = &db[&42];
+        assert_eq!(3, stage.index);
+        assert_eq!(1000, stage.timestamp_ms);
+        assert_eq!(Stage::default().ef, stage.ef);
+    }
+
+    #[test]
+    fn decode_text_rejects_a_database_from_a_newer_schema_version() {
+        let future_version = CURRENT_SCHEMA_VERSION + 1;
+        let bytes = format!(
+            "{};{};{}\n42;3;1000;2.5;0;0\n",
+            APP_ID, CURRENT_FORMAT_VERSION, future_version
+        );
+
+        let result = decode_text(bytes.as_bytes());
+        assert!(matches!(result, Err(Error::UnsupportedVersion(v)) if v == future_version));
+    }
+
+    #[test]
+    fn decode_text_rejects_a_database_from_a_newer_format_version() {
+        let future_version = CURRENT_FORMAT_VERSION + 1;
+        let bytes = format!(
+            "{};{};{}\n42;3;1000;2.5;0;0\n",
+            APP_ID, future_version, CURRENT_SCHEMA_VERSION
+        );
+
+        let result = decode_text(bytes.as_bytes());
+        assert!(matches!(result, Err(Error::UnsupportedFormat(v)) if v == future_version));
+    }
+
+    #[test]
+    fn append_replays_keeping_the_last_record_per_hash() {
+        let fname = "./sample_journal_test.txt";
+        let _ = std::fs::remove_file(fname);
+
+        let first = Stage { index: 1, timestamp_ms: 1, ..Stage::default() };
+        let second = Stage { index: 2, timestamp_ms: 2, ..Stage::default() };
+
+        append(fname, 7, &first).expect("error appending to the journal");
+        append(fname, 7, &second).expect("error appending to the journal");
+
+        let db = load(fname).expect("error replaying the journal");
+        assert_eq!(1, db.len());
+        assert_eq!(2, db[&7].index);
+
+        compact(fname, 0).expect("error compacting the journal");
+        let compacted = load(fname).expect("error loading the compacted journal");
+        assert_eq!(1, compacted.len());
+        assert_eq!(2, compacted[&7].index);
+
+        let _ = std::fs::remove_file(fname);
+    }
+
+    struct MemoryProgressStore {
+        db: std::sync::Mutex<HashMap<u64, Stage>>,
+    }
+
+    impl AsyncProgressStore for MemoryProgressStore {
+        fn load_async(
+            &self,
+        ) -> Pin<Box<dyn Future<Output = Result<HashMap<u64, Stage>, Error>> + Send>> {
+            let db = self.db.lock().unwrap().clone();
+            Box::pin(async move { Ok(db) })
+        }
+
+        fn save_async(
+            &self,
+            progress: HashMap<u64, Stage>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> {
+            *self.db.lock().unwrap() = progress;
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn blocking_adapter_drives_an_async_store_to_completion() {
+        let store = Blocking(MemoryProgressStore { db: std::sync::Mutex::new(HashMap::new()) });
+
+        let mut db = HashMap::new();
+        db.insert(42, Stage { index: 3, ..Stage::default() });
+        store.save(&db).expect("error saving through the blocking adapter");
+
+        let loaded = store.load().expect("error loading through the blocking adapter");
+        assert_eq!(3, loaded[&42].index);
+    }
+
     // TODO: use different file
     //#[test]
     fn load_and_save_db_with_updated_stage() {
-        let mut db = load("./sample_db.txt");
+        let mut db = load("./sample_db.txt").expect("error loading progress database");
         assert_eq!(20, db.len());
 
         db.insert(
             9228782626062525010,
-            Stage { index: 3, timestamp_ms: time::get_unix_time_millis() },
+            Stage { index: 3, timestamp_ms: time::get_unix_time_millis(), ..Stage::default() },
         );
         assert_eq!(20, db.len());
-        save(&db, "./sample_db2.txt");
+        save(&db, "./sample_db2.txt").expect("error saving progress database");
     }
 }
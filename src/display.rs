@@ -1,22 +1,23 @@
 //! A display for the terminal.
+use crate::backend::{is_peek_key, is_quit_key};
+use crate::backend::{Backend, BackendEvent, Cell, CrosstermBackend};
 use crate::cardbox::Progress;
 use crate::constants::BLANK_INDICATOR;
 use crate::constants::NUM_REVEALED_CHARS_IN_HINT;
 use crate::constants::{APP_NAME, APP_VERSION, HEADER_HEIGHT};
 use crate::constants::{BG_COLOR, FG_COLOR};
-use crate::constants::{PROGRAM_PEEK_KEY, PROGRAM_QUIT_KEY};
-use crate::constants::{PROMPT_INPUT, PROMPT_WIDTH};
+use crate::constants::CARD_TIME_LIMIT_SECS;
+use crate::constants::PROMPT_WIDTH;
 use crate::flashcards::*;
 use crate::validator::{HintMode, InputValidator};
 
-#[cfg(not(debug_assertions))]
-use crossterm::AlternateScreen;
-#[cfg(debug_assertions)]
-use crossterm::RawScreen;
+use crossterm::ClearType;
+use crossterm::KeyEvent;
 
-use crossterm::Colored;
-use crossterm::{ClearType, Terminal, TerminalCursor, TerminalInput};
-use crossterm::{InputEvent, KeyEvent};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use std::time::{Duration, Instant};
 
 // Re-export Color
 pub use crossterm::Color;
@@ -28,6 +29,7 @@ type InputLocation = (u16, u16);
 struct InputLocations
 {
     pub locations: Vec<InputLocation>,
+    widths: Vec<u16>,
     index: usize,
     length: usize,
 }
@@ -37,42 +39,38 @@ impl InputLocations
     // Creates a new instance.
     //
     // Wherever `text` contains a `BLANK_INDICATOR` it will create an `InputLocation`.
-    pub fn new(x: u16, y: u16, w: u16, h: u16, text: &str) -> Self
+    //
+    // Walks `text` grapheme by grapheme (not byte or `char`) so combining marks and
+    // multi-byte scalars don't inflate the column count, and advances by each
+    // grapheme's display width so full-width characters take up their two columns,
+    // wrapping to the next row once a grapheme would overflow `w`.
+    pub fn new(x: u16, y: u16, w: u16, _h: u16, text: &str) -> Self
     {
         let mut locations = vec![];
-
-        let num_chars = text.len() as u16;
-
-        // Count required lines
-        let mut num_lines = num_chars / w;
-        if num_chars % w != 0 {
-            num_lines += 1;
-        }
+        let mut widths = vec![];
 
         let mut u = x;
         let mut v = y;
 
-        for (i, c) in text.chars().enumerate() {
-            let i = i as u16;
+        for grapheme in text.graphemes(true) {
+            let width = UnicodeWidthStr::width(grapheme).max(1) as u16;
 
-            // Add input location
-            if c == BLANK_INDICATOR {
-                locations.push((u, v));
-            }
-
-            //
-            if (i + 1) % w == 0 {
-                // newline
+            if u > x && u + width > w {
                 u = x;
                 v += 1;
-            } else {
-                u += 1;
             }
+
+            if grapheme == BLANK_INDICATOR.to_string() {
+                locations.push((u, v));
+                widths.push(width);
+            }
+
+            u += width;
         }
 
         let length = locations.len();
 
-        Self { locations, index: 0, length }
+        Self { locations, widths, index: 0, length }
     }
 
     /// Moves to the first cursor location.
@@ -84,6 +82,12 @@ impl InputLocations
         self.locations[0]
     }
 
+    /// Returns the display width of the grapheme occupying the current location.
+    pub fn current_width(&self) -> u16
+    {
+        self.widths.get(self.index).copied().unwrap_or(1)
+    }
+
     /// Moves to the next cursor location.
     pub fn next(&mut self) -> Option<InputLocation>
     {
@@ -111,151 +115,317 @@ impl InputLocations
     }
 }
 
+/// A `width*height` grid of `Cell`s, diffed against the previous frame on `flush()` so
+/// only the cells that actually changed are redrawn. Eliminates the flicker of clearing
+/// and reprinting whole line ranges on every redraw.
+struct Buffer
+{
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    previous: Vec<Cell>,
+}
+
+impl Buffer
+{
+    fn new(width: u16, height: u16) -> Self
+    {
+        let size = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); size],
+            previous: vec![Cell::default(); size],
+        }
+    }
+
+    /// Marks every cell as blank, in both the current and previous frame, so the next
+    /// `flush()` has nothing to redraw (the caller is expected to have already cleared
+    /// the real terminal).
+    fn clear_all(&mut self)
+    {
+        for cell in &mut self.cells {
+            *cell = Cell::default();
+        }
+        self.previous.copy_from_slice(&self.cells);
+    }
+
+    /// Marks row `y` as blank, in both the current and previous frame.
+    fn clear_row(&mut self, y: u16)
+    {
+        if y >= self.height {
+            return;
+        }
+        let start = y as usize * self.width as usize;
+        let end = start + self.width as usize;
+        for cell in &mut self.cells[start..end] {
+            *cell = Cell::default();
+        }
+        self.previous[start..end].copy_from_slice(&self.cells[start..end]);
+    }
+
+    fn set(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color)
+    {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = y as usize * self.width as usize + x as usize;
+        self.cells[index] = Cell { ch, fg, bg };
+    }
+
+    /// Writes `text` into the buffer starting at `(x, y)`, advancing one column per
+    /// character.
+    fn write(&mut self, x: u16, y: u16, text: &str, fg: Color, bg: Color)
+    {
+        let mut cx = x;
+        for ch in text.chars() {
+            self.set(cx, y, ch, fg, bg);
+            cx += 1;
+        }
+    }
+
+    /// Diffs the current frame against the previous one and emits the minimal
+    /// `goto`+`write` runs needed to bring `backend` up to date.
+    fn flush<B: Backend>(&mut self, backend: &mut B)
+    {
+        for y in 0..self.height {
+            let mut x = 0;
+
+            while x < self.width {
+                let index = y as usize * self.width as usize + x as usize;
+                if self.cells[index] == self.previous[index] {
+                    x += 1;
+                    continue;
+                }
+
+                let run_start = x;
+                let fg = self.cells[index].fg;
+                let bg = self.cells[index].bg;
+                let mut run = String::new();
+
+                while x < self.width {
+                    let index = y as usize * self.width as usize + x as usize;
+                    if self.cells[index] == self.previous[index]
+                        || self.cells[index].fg != fg
+                        || self.cells[index].bg != bg
+                    {
+                        break;
+                    }
+                    run.push(self.cells[index].ch);
+                    x += 1;
+                }
+
+                backend.goto(run_start, y).expect("error moving cursor");
+                backend.write(&run, fg, bg).expect("error writing to terminal");
+            }
+        }
+
+        self.previous.copy_from_slice(&self.cells);
+    }
+}
+
+/// An event `Display`'s input loops react to: a key press to hand back to the caller, a
+/// resize that `Display` has already applied to itself, or a heartbeat the loop can use
+/// to animate while it waits for the next keystroke.
+enum DisplayEvent
+{
+    Key(KeyEvent),
+    Resized,
+    Tick,
+    Paste(String),
+}
+
 /// Realizes a terminal based UI for this application.
-pub struct Display
+///
+/// Generic over the `Backend` driving the terminal, so the real `CrosstermBackend` can be
+/// swapped for a `TestBackend` in tests.
+pub struct Display<B: Backend = CrosstermBackend>
 {
-    terminal: Terminal,
-    cursor: TerminalCursor,
-    input: TerminalInput,
-    #[cfg(not(debug_assertions))]
-    _alt: AlternateScreen,
-    #[cfg(debug_assertions)]
-    _raw: crossterm::RawScreen,
-    width: usize,
-    height: usize,
+    backend: B,
+    buffer: Buffer,
+    cursor: (u16, u16),
+    /// The cursor position right before a hint peek started, so `clear_hint` can put it
+    /// back where the user was typing instead of wherever `clear_until_footer` happens
+    /// to leave it.
+    peek_origin: Option<(u16, u16)>,
+    width: std::cell::Cell<u16>,
+    height: std::cell::Cell<u16>,
 }
 
-impl Display
+impl Display<CrosstermBackend>
 {
-    /// Creates a new display.
+    /// Creates a new display, backed by a real terminal.
     pub fn new() -> Self
     {
-        #[cfg(not(debug_assertions))]
-        let _alt = AlternateScreen::to_alternate(true)
-            .expect("error creating alternate raw screen");
-        #[cfg(debug_assertions)]
-        let _raw = RawScreen::into_raw_mode().expect("error creating raw screen");
-
-        let terminal = crossterm::terminal();
-        let cursor = crossterm::cursor();
-        let input = crossterm::input();
+        Self::with_backend(CrosstermBackend::new())
+    }
+}
 
-        let (width, height) = terminal.terminal_size();
+impl<B: Backend> Display<B>
+{
+    /// Creates a new display driven by the given `Backend`.
+    pub fn with_backend(mut backend: B) -> Self
+    {
+        backend.enter().expect("error entering terminal drawing mode");
+        let (width, height) = backend.size();
 
         Self {
-            terminal,
-            cursor,
-            input,
-            #[cfg(not(debug_assertions))]
-            _alt,
-            #[cfg(debug_assertions)]
-            _raw,
-            width: width as usize,
-            height: height as usize,
+            backend,
+            buffer: Buffer::new(width, height),
+            cursor: (0, 0),
+            peek_origin: None,
+            width: std::cell::Cell::new(width),
+            height: std::cell::Cell::new(height),
         }
     }
 
+    /// Returns the current width of the terminal, in columns.
+    fn width(&self) -> u16
+    {
+        self.width.get()
+    }
+
+    /// Returns the current height of the terminal, in rows.
+    fn height(&self) -> u16
+    {
+        self.height.get()
+    }
+
     /// Initializes the display.
-    pub fn init(&self)
+    pub fn init(&mut self)
     {
         self.hide_cursor();
         self.clear();
         self.print_header();
         self.print_footer();
-        self.cursor.goto(0, HEADER_HEIGHT).expect("error moving cursor");
+        self.goto(0, HEADER_HEIGHT);
     }
 
     /// Redraws the display.
-    pub fn redraw(&self)
+    pub fn redraw(&mut self)
     {
         self.clear_input_area();
-        self.cursor.goto(0, HEADER_HEIGHT).expect("error moving cursor");
+        self.goto(0, HEADER_HEIGHT);
+    }
+
+    /// Remembers the terminal size carried by a resize event, and redraws the
+    /// header/footer so the layout reflects the new dimensions.
+    fn handle_resize(&mut self, width: u16, height: u16)
+    {
+        self.width.set(width);
+        self.height.set(height);
+        self.buffer = Buffer::new(width, height);
+
+        self.clear();
+        self.print_header();
+        self.print_footer();
     }
 
     /// Prints the header of this display.
-    pub fn print_header(&self)
+    pub fn print_header(&mut self)
     {
-        self.print_bar_top(BG_COLOR, self.width);
+        self.print_bar_top(BG_COLOR, self.width());
         self.print_title(FG_COLOR, BG_COLOR);
 
         // one empty line (just for style)
-        println!();
+        self.println_cr("");
     }
 
     /// Prints the footer of this display.
-    pub fn print_footer(&self)
+    pub fn print_footer(&mut self)
     {
-        self.print_bar_bot(BG_COLOR, self.width);
+        self.print_bar_bot(BG_COLOR, self.width());
         self.print_shortcuts(FG_COLOR, BG_COLOR);
     }
 
     /// Prints a notification that the flashcard was correctly answered.
-    pub fn print_passed(&self)
+    pub fn print_passed(&mut self)
     {
         let x = 1;
-        let y = self.height as u16 - 1;
+        let y = self.height() - 1;
         self.cprint_at("Passed", x, y, Color::Green, Color::Reset);
     }
 
     /// Prints a notification that the flashcard was not correctly answered.
-    pub fn print_failed(&self)
+    pub fn print_failed(&mut self)
     {
         let x = 1;
-        let y = self.height as u16 - 1;
+        let y = self.height() - 1;
         self.cprint_at("Failed", x, y, Color::Red, Color::Reset);
     }
 
-    fn print_bar_top(&self, bg_color: Color, width: usize)
+    /// Redraws the per-card countdown in the header and reports whether `deadline` has
+    /// passed.
+    ///
+    /// Called on every `Tick` while an input loop is waiting for a keystroke, so the
+    /// countdown keeps moving even though nothing was typed.
+    fn on_tick(&mut self, deadline: Instant) -> bool
+    {
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) => {
+                self.print_countdown(remaining.as_secs());
+                false
+            }
+            None => {
+                self.print_countdown(0);
+                true
+            }
+        }
+    }
+
+    /// Prints the number of seconds left to answer the current flashcard, right-aligned
+    /// in the header.
+    fn print_countdown(&mut self, remaining_secs: u64)
     {
-        self.cursor.save_position().expect("error saving cursor position");
-        self.cursor.goto(0, 0).expect("error moving cursor");
-        let empty_line = format!("{: <1$}", "", width + 1);
-        println!(
-            "\r{}{}{}",
-            Colored::Bg(bg_color),
-            empty_line,
-            Colored::Bg(Color::Reset)
-        );
-        self.cursor.reset_position().expect("error resetting cusor position");
+        let text = format!("{:>3}s", remaining_secs);
+        let x = self.width() - text.len() as u16 - 1;
+        self.cprint_at(text, x, HEADER_HEIGHT - 1, FG_COLOR, BG_COLOR);
     }
 
-    fn print_bar_bot(&self, bg_color: Color, width: usize)
+    fn print_bar_top(&mut self, bg_color: Color, width: u16)
     {
-        self.cursor.save_position().expect("error saving cursor position");
-        self.cursor.goto(0, self.height as u16).expect("error moving cursor");
-        let empty_line = format!("{: <1$}", "", width + 1);
-        print!("\r{}{}{}", Colored::Bg(bg_color), empty_line, Colored::Bg(Color::Reset));
-        self.cursor.reset_position().expect("error resetting cusor position");
+        let saved = self.cursor;
+        self.goto(0, 0);
+        let empty_line = format!("{: <1$}", "", width as usize + 1);
+        self.cprintln_cr(empty_line, Color::Reset, bg_color);
+        self.goto(saved.0, saved.1);
     }
 
-    fn print_title(&self, fg: Color, bg: Color)
+    fn print_bar_bot(&mut self, bg_color: Color, width: u16)
+    {
+        let saved = self.cursor;
+        self.goto(0, self.height());
+        let empty_line = format!("{: <1$}", "", width as usize + 1);
+        self.cprint_cr_bg(empty_line, Color::Reset, bg_color);
+        self.goto(saved.0, saved.1);
+    }
+
+    fn print_title(&mut self, fg: Color, bg: Color)
     {
         let name_version = format!("{} {}", APP_NAME, APP_VERSION);
         let x = 1;
         self.cprint_at(name_version, x, 0, fg, bg);
     }
 
-    fn print_shortcuts(&self, fg: Color, bg: Color)
+    fn print_shortcuts(&mut self, fg: Color, bg: Color)
     {
         let shortcuts = format!(
             "{} | {} | {}",
             "RETURN: next flashcard", "CTRL-Q: quit program", "CTRL-P: peek at solution"
         );
         let x = 1;
-        let y = self.height as u16;
+        let y = self.height();
 
         self.cprint_at(shortcuts, x, y, fg, bg);
     }
 
     /// Prints the progress.
-    pub fn print_progress(&self, progress: Progress)
+    pub fn print_progress(&mut self, progress: Progress)
     {
-        let stages = format!(
-            "|{}|{}|{}|{}|{}| left: {}",
-            progress.1, progress.2, progress.3, progress.4, progress.5, progress.0
-        );
+        let counts = progress.stages.iter().map(usize::to_string).collect::<Vec<_>>().join("|");
+        let stages = format!("|{}| left: {}", counts, progress.new);
         let w = stages.len();
-        let x = self.width as u16 - w as u16;
+        let x = self.width() - w as u16;
 
         self.cprint_at(stages, x, 0, FG_COLOR, BG_COLOR);
     }
@@ -271,16 +441,16 @@ impl Display
 
         let x = PROMPT_WIDTH + 1;
 
-        let (_, y) = self.cursor.pos();
-        let (w, h) = (self.width as u16, self.height as u16);
+        let (origin_x, origin_y) = self.cursor;
+        let (mut w, mut h) = (self.width(), self.height());
 
-        self.cursor.goto(x, y).expect("error moving cursor");
+        self.goto(x, origin_y);
 
         if cfg!(debug_assertions) {
             self.cprint_at(
-                format!("x={}, y={}, w={}, h={}", x, y, w, h),
+                format!("x={}, y={}, w={}, h={}", x, origin_y, w, h),
                 1,
-                self.height as u16 - 1,
+                self.height() - 1,
                 Color::Black,
                 Color::White,
             );
@@ -288,79 +458,112 @@ impl Display
 
         // Based on terminal size, cursor position calculate a position for each character
         // that needs to be entered by the user
-        let mut locations = InputLocations::new(x, y, w, h, &context);
+        let mut locations = InputLocations::new(x, origin_y, w, h, &context);
 
         if cfg!(debug_assertions) {
             self.cprint_at(
                 format!("locations={:?}", locations.locations),
                 30,
-                self.height as u16 - 1,
+                self.height() - 1,
                 Color::Black,
                 Color::White,
             );
         }
 
         let (x, y) = locations.first();
-        self.cursor.goto(x, y).expect("error moving cursor");
+        self.goto(x, y);
 
         self.show_cursor();
-        let mut reader = self.input.read_sync();
-
-        'outer: loop {
-            for input in reader.next() {
-                match input {
-                    InputEvent::Keyboard(e) => match e {
-                        // IGNORING
-                        KeyEvent::Char(c) if c as u8 == 10 => (), // Ignore <ENTER>
-
-                        // WRITING
-                        KeyEvent::Char(c) => {
-                            // only allow typing if the validator still accepts more
-                            // characters
+
+        let deadline = Instant::now() + Duration::from_secs(CARD_TIME_LIMIT_SECS);
+
+        loop {
+            let event = match self.next_event() {
+                Some(event) => event,
+                None => continue,
+            };
+
+            let key = match event {
+                DisplayEvent::Resized => {
+                    w = self.width();
+                    h = self.height();
+                    locations = InputLocations::new(x, origin_y, w, h, &context);
+                    let (x, y) = locations.first();
+                    self.goto(x, y);
+                    continue;
+                }
+                DisplayEvent::Tick => {
+                    if self.on_tick(deadline) {
+                        validator.fail();
+                        self.hide_cursor();
+                        return true;
+                    }
+                    continue;
+                }
+                DisplayEvent::Paste(text) => {
+                    // Walk grapheme by grapheme so a pasted multi-byte character still
+                    // only consumes a single `InputLocation`.
+                    for grapheme in text.graphemes(true) {
+                        for ch in grapheme.chars() {
                             if validator.accepts() {
-                                if validator.check(c) {
-                                    self.cprint(c, Color::Green);
+                                if validator.check(ch) {
+                                    self.cprint(ch, Color::Green);
                                 } else {
-                                    self.cprint(c, Color::Red);
+                                    self.cprint(ch, Color::Red);
                                 }
                             }
-
-                            if let Some((x, y)) = locations.next() {
-                                self.cursor.goto(x, y).expect("error moving cursor");
-                            }
                         }
 
-                        // QUITTING
-                        KeyEvent::Ctrl(c) if c == PROGRAM_QUIT_KEY => {
-                            self.exit();
-                            return false;
+                        if let Some((x, y)) = locations.next() {
+                            self.goto(x, y);
                         }
+                    }
+                    continue;
+                }
+                DisplayEvent::Key(key) => key,
+            };
+
+            match key {
+                // IGNORING
+                KeyEvent::Char(c) if c as u8 == 10 => (), // Ignore <ENTER>
+
+                // WRITING
+                KeyEvent::Char(c) => {
+                    // only allow typing if the validator still accepts more
+                    // characters
+                    if validator.accepts() {
+                        if validator.check(c) {
+                            self.cprint(c, Color::Green);
+                        } else {
+                            self.cprint(c, Color::Red);
+                        }
+                    }
 
-                        // UNDOING
-                        KeyEvent::Backspace => {
-                            if validator.index() > 0 {
-                                validator.undo(1);
-
-                                if let Some((x, y)) = locations.prev() {
-                                    /*
-                                    self.cprint_at(
-                                        "undo",
-                                        1,
-                                        self.height as u16 - 1,
-                                        Color::White,
-                                        Color::Blue,
-                                    );
-                                    */
-                                    self.cursor.goto(x, y).expect("error moving cursor");
-                                    self.print(BLANK_INDICATOR);
-                                    self.cursor.goto(x, y).expect("error moving cursor");
-                                }
-                            }
+                    if let Some((x, y)) = locations.next() {
+                        self.goto(x, y);
+                    }
+                }
+
+                // QUITTING
+                key if is_quit_key(&key) => {
+                    self.exit();
+                    return false;
+                }
+
+                // UNDOING
+                KeyEvent::Backspace => {
+                    if validator.index() > 0 {
+                        validator.undo(1);
+
+                        if let Some((x, y)) = locations.prev() {
+                            self.goto(x, y);
+                            let width = locations.current_width() as usize;
+                            self.print(BLANK_INDICATOR.to_string().repeat(width));
+                            self.goto(x, y);
                         }
-                        _ => (),
-                    },
-                    _ => (),
+                    }
                 }
+                _ => (),
             }
         }
 
@@ -376,89 +579,99 @@ impl Display
     {
         self.show_cursor();
 
-        let mut reader = self.input.read_sync();
-
-        'outer: loop {
-            for input in reader.next() {
-                match input {
-                    InputEvent::Keyboard(e) => match e {
-                        // IGNORING
-                        KeyEvent::Char(ch) if ch as u8 == 10 => (), //Ignore <ENTER>
-
-                        // WRITING
-                        KeyEvent::Char(ch) => {
-                            // if the user starts typing remove the hint if shown
-                            match validator.hint_mode() {
-                                HintMode::Active(_) => {
-                                    self.clear_hint(validator);
-                                }
-                                _ => (),
-                            }
-
-                            // only allow typing if the validator still accepts more
-                            // characters
-                            if validator.accepts() {
-                                if validator.check(ch) {
-                                    self.cprint(ch, Color::Green);
-                                } else {
-                                    self.cprint(ch, Color::Red);
-                                }
-                            }
+        let deadline = Instant::now() + Duration::from_secs(CARD_TIME_LIMIT_SECS);
+
+        loop {
+            let event = match self.next_event() {
+                Some(event) => event,
+                None => continue,
+            };
+
+            let key = match event {
+                DisplayEvent::Resized => continue,
+                DisplayEvent::Tick => {
+                    if self.on_tick(deadline) {
+                        validator.fail();
+                        self.hide_cursor();
+                        return true;
+                    }
+                    continue;
+                }
+                DisplayEvent::Paste(text) => {
+                    self.feed_paste(validator, &text);
+
+                    if validator.happy() {
+                        self.println_cr("");
+                        break;
+                    }
+                    continue;
+                }
+                DisplayEvent::Key(key) => key,
+            };
+
+            match key {
+                // IGNORING
+                KeyEvent::Char(ch) if ch as u8 == 10 => (), //Ignore <ENTER>
+
+                // WRITING
+                KeyEvent::Char(ch) => {
+                    // if the user starts typing remove the hint if shown
+                    if let HintMode::Active(_) = validator.hint_mode() {
+                        self.clear_hint(validator);
+                    }
+
+                    // only allow typing if the validator still accepts more
+                    // characters
+                    if validator.accepts() {
+                        if validator.check(ch) {
+                            self.cprint(ch, Color::Green);
+                        } else {
+                            self.cprint(ch, Color::Red);
                         }
+                    }
+                }
 
-                        // QUITTING
-                        KeyEvent::Ctrl(ch) if ch == PROGRAM_QUIT_KEY => {
-                            self.exit();
-                            return false;
-                        }
+                // QUITTING
+                key if is_quit_key(&key) => {
+                    self.exit();
+                    return false;
+                }
 
-                        // UNDOING
-                        KeyEvent::Backspace => {
-                            match validator.hint_mode() {
-                                HintMode::Active(_) => {
-                                    self.clear_hint(validator);
-                                }
-                                _ => (),
-                            }
+                // UNDOING
+                KeyEvent::Backspace => {
+                    if let HintMode::Active(_) = validator.hint_mode() {
+                        self.clear_hint(validator);
+                    }
+
+                    if validator.index() > 0 {
+                        validator.undo(1);
+                        // BUG: if multiline, move cursor up and to the right
+                        let (x, y) = self.cursor;
+                        self.goto(x.saturating_sub(1), y);
+                        self.backend
+                            .clear(ClearType::UntilNewLine)
+                            .expect("error clearing display");
+                    }
+                }
 
-                            if validator.index() > 0 {
-                                validator.undo(1);
-                                // BUG: if multiline, move cursor up and to the right
-                                self.cursor.move_left(1);
-                                self.terminal
-                                    .clear(ClearType::UntilNewLine)
-                                    .expect("error clearing display");
-                            }
+                // PEEKING
+                key if is_peek_key(&key) => {
+                    if let HintMode::Inactive = validator.hint_mode() {
+                        self.clear_incorrect(validator);
+                        self.peek_origin = Some(self.cursor);
+                    }
+                    for _ in 0..NUM_REVEALED_CHARS_IN_HINT {
+                        if let Some(c) = validator.peek() {
+                            self.cprint(c, Color::Yellow);
                         }
-
-                        // PEEKING
-                        KeyEvent::Ctrl(c) if c == PROGRAM_PEEK_KEY => {
-                            match validator.hint_mode() {
-                                HintMode::Inactive => {
-                                    //
-                                    self.clear_incorrect(validator);
-
-                                    // go back to the last correct char or to index 0
-                                    self.cursor
-                                        .save_position()
-                                        .expect("error saving position");
-                                }
-                                _ => (),
-                            }
-                            for _ in 0..NUM_REVEALED_CHARS_IN_HINT {
-                                if let Some(c) = validator.peek() {
-                                    self.cprint(c, Color::Yellow);
-                                }
-                            }
-                        }
-                        _ => (),
-                    },
-                    _ => (),
-                }
-                if validator.happy() {
-                    self.println_cr("");
-                    break 'outer;
+                    }
                 }
+                _ => (),
+            }
+
+            if validator.happy() {
+                self.println_cr("");
+                break;
             }
         }
 
@@ -466,47 +679,76 @@ impl Display
         true
     }
 
+    /// Blocks until the next input event arrives from the backend, transparently
+    /// applying resize events before handing them back to the caller.
+    fn next_event(&mut self) -> Option<DisplayEvent>
+    {
+        match self.backend.read_event()? {
+            BackendEvent::Key(key) => Some(DisplayEvent::Key(key)),
+            BackendEvent::Resize(width, height) => {
+                self.handle_resize(width, height);
+                Some(DisplayEvent::Resized)
+            }
+            BackendEvent::Tick => Some(DisplayEvent::Tick),
+            BackendEvent::Paste(text) => Some(DisplayEvent::Paste(text)),
+        }
+    }
+
+    /// Moves the cursor to `(x, y)`, remembering the new position.
+    fn goto(&mut self, x: u16, y: u16)
+    {
+        self.backend.goto(x, y).expect("error moving cursor");
+        self.cursor = (x, y);
+    }
+
     /// Prints text to the terminal without newline character.
-    pub fn print(&self, text: impl std::fmt::Display)
+    pub fn print(&mut self, text: impl std::fmt::Display)
     {
-        self.terminal.write(format!("{}", text)).expect("error writing to terminal");
+        self.write(&format!("{}", text), FG_COLOR, BG_COLOR);
     }
 
     /// Prints text to the terminal without newline character after carriage return.
-    pub fn print_cr(&self, text: impl std::fmt::Display)
+    pub fn print_cr(&mut self, text: impl std::fmt::Display)
     {
-        self.terminal.write(format!("\r{}", text)).expect("error writing to terminal");
+        self.write(&format!("\r{}", text), FG_COLOR, BG_COLOR);
     }
 
     /// Prints colored text to the terminal without newline character.
-    pub fn cprint(&self, text: impl std::fmt::Display, color: Color)
+    pub fn cprint(&mut self, text: impl std::fmt::Display, color: Color)
     {
-        print!("{}{}{}", Colored::Fg(color), text, Colored::Fg(Color::Reset));
+        self.write(&format!("{}", text), color, Color::Reset);
     }
 
     /// Prints colored text to the terminal without newline character after carriage
     /// return.
-    pub fn cprint_cr(&self, text: impl std::fmt::Display, color: Color)
+    pub fn cprint_cr(&mut self, text: impl std::fmt::Display, color: Color)
     {
-        print!("\r{}{}{}", Colored::Fg(color), text, Colored::Fg(Color::Reset));
+        self.write(&format!("\r{}", text), color, Color::Reset);
     }
 
     /// Prints colored text to the terminal without newline character after carriage
+    /// return, using both a foreground and background color.
+    fn cprint_cr_bg(&mut self, text: impl std::fmt::Display, fg: Color, bg: Color)
+    {
+        self.write(&format!("\r{}", text), fg, bg);
+    }
+
+    /// Prints colored text to the terminal with a newline character after carriage
     /// return.
-    pub fn cprintln_cr(&self, text: impl std::fmt::Display, color: Color)
+    pub fn cprintln_cr(&mut self, text: impl std::fmt::Display, fg: Color, bg: Color)
     {
-        println!("\r{}{}{}", Colored::Fg(color), text, Colored::Fg(Color::Reset));
+        self.write(&format!("\r{}\n", text), fg, bg);
     }
 
     /// Prints text to the terminal with a newline character after carriage return.
-    pub fn println_cr(&self, text: impl std::fmt::Display)
+    pub fn println_cr(&mut self, text: impl std::fmt::Display)
     {
-        self.terminal.write(format!("\r{}\n", text)).expect("error writing to terminal");
+        self.write(&format!("\r{}\n", text), FG_COLOR, BG_COLOR);
     }
 
     /// Prints colored text to the terminal at a certain position.
     pub fn cprint_at(
-        &self,
+        &mut self,
         text: impl std::fmt::Display,
         x: u16,
         y: u16,
@@ -514,94 +756,140 @@ impl Display
         bg_color: Color,
     )
     {
-        let (ox, oy) = self.cursor.pos();
+        let saved = self.cursor;
 
-        self.cursor.goto(x, y).expect("couldn't move cursor");
-        print!(
-            "{}{}{}{}{}",
-            Colored::Bg(bg_color),
-            Colored::Fg(fg_color),
-            text,
-            Colored::Fg(Color::Reset),
-            Colored::Bg(Color::Reset)
-        );
+        self.goto(x, y);
+        self.write(&format!("{}", text), fg_color, bg_color);
 
-        self.cursor.goto(ox, oy).expect("couldn't move cursor");
+        self.goto(saved.0, saved.1);
+    }
+
+    /// Writes `text` into the buffer at the current cursor position and flushes the
+    /// frame, redrawing only the cells that actually changed. Honors `\r`/`\n` the same
+    /// way a real terminal would, so callers can keep embedding them in formatted text.
+    fn write(&mut self, text: &str, fg: Color, bg: Color)
+    {
+        let (mut x, mut y) = self.cursor;
+
+        for ch in text.chars() {
+            match ch {
+                '\r' => x = 0,
+                '\n' => y += 1,
+                ch => {
+                    self.buffer.set(x, y, ch, fg, bg);
+                    x += 1;
+                }
+            }
+        }
+
+        self.cursor = (x, y);
+        self.buffer.flush(&mut self.backend);
     }
 
     /// Ignores all input except <RETURN> and <CRTL-C>
-    pub fn wait_for_return(&self) -> bool
-    {
-        let mut reader = self.input.read_sync();
-        'outer: loop {
-            for c in reader.next() {
-                match c {
-                    InputEvent::Keyboard(e) => match e {
-                        KeyEvent::Char(c) if c as u8 == 10 => break 'outer, // <RETURN>
-                        KeyEvent::Ctrl(c) if c == PROGRAM_QUIT_KEY => {
-                            self.exit();
-                            return true;
-                        }
-                        _ => (),
-                    },
-                    _ => (),
+    pub fn wait_for_return(&mut self) -> bool
+    {
+        loop {
+            let event = match self.next_event() {
+                Some(event) => event,
+                None => continue,
+            };
+
+            let key = match event {
+                DisplayEvent::Resized => continue,
+                DisplayEvent::Tick => continue,
+                DisplayEvent::Paste(_) => continue,
+                DisplayEvent::Key(key) => key,
+            };
+
+            match key {
+                KeyEvent::Char(c) if c as u8 == 10 => break, // <RETURN>
+                key if is_quit_key(&key) => {
+                    self.exit();
+                    return true;
                 }
+                _ => (),
             }
         }
         false
     }
 
-    fn exit(&self)
+    fn exit(&mut self)
     {
-        RawScreen::disable_raw_mode().expect("error disabling raw-mode");
-        self.show_cursor();
+        self.backend.leave().expect("error leaving terminal drawing mode");
     }
 
-    fn hide_cursor(&self)
+    fn hide_cursor(&mut self)
     {
-        self.cursor.hide().expect("error hiding cursor");
+        self.backend.hide_cursor().expect("error hiding cursor");
     }
 
-    fn show_cursor(&self)
+    fn show_cursor(&mut self)
     {
-        self.cursor.show().expect("error showing cursor");
+        self.backend.show_cursor().expect("error showing cursor");
     }
 
     /// Clears the complete terminal. Should be called early.
-    fn clear(&self)
+    fn clear(&mut self)
     {
-        self.terminal.clear(ClearType::All).expect("error clearing display");
+        self.backend.clear(ClearType::All).expect("error clearing display");
+        self.buffer.clear_all();
     }
 
     /// Clears everything except the header.
-    fn clear_input_area(&self)
+    fn clear_input_area(&mut self)
     {
-        self.cursor.goto(2, HEADER_HEIGHT).expect("error moving cursor");
+        self.goto(2, HEADER_HEIGHT);
         self.clear_until_footer();
     }
 
     /// Clears until the footer begins.
     ///
     /// This method doesn't clear the footer so it doesn't need to be redrawn.
-    fn clear_until_footer(&self)
-    {
-        let (_, current_y) = self.cursor.pos();
-        self.cursor.save_position().expect("error saving cursor position");
-        self.terminal.clear(ClearType::UntilNewLine).expect("error clearing line");
-        for y in (current_y + 1)..(self.height as u16) {
-            self.cursor.goto(0, y).expect("error moving cursor");
-            self.terminal.clear(ClearType::CurrentLine).expect("error clearing line");
+    fn clear_until_footer(&mut self)
+    {
+        let saved = self.cursor;
+        let (_, current_y) = self.cursor;
+
+        self.backend.clear(ClearType::UntilNewLine).expect("error clearing line");
+        self.buffer.clear_row(current_y);
+        for y in (current_y + 1)..self.height() {
+            self.goto(0, y);
+            self.backend.clear(ClearType::CurrentLine).expect("error clearing line");
+            self.buffer.clear_row(y);
         }
-        self.cursor.reset_position().expect("error restoring cursor position");
+
+        self.goto(saved.0, saved.1);
     }
 
-    /// This function is used to remove the hint once the user starts typing again
-    fn clear_hint(&self, validator: &mut InputValidator)
+    /// Feeds a pasted string through `validator` one character at a time, echoing each
+    /// as green/red, so a paste validates exactly like typing it would have.
+    fn feed_paste(&mut self, validator: &mut InputValidator, text: &str)
     {
-        self.cursor.reset_position().expect("error resetting postion");
+        for ch in text.chars() {
+            if let HintMode::Active(_) = validator.hint_mode() {
+                self.clear_hint(validator);
+            }
+
+            if validator.accepts() {
+                if validator.check(ch) {
+                    self.cprint(ch, Color::Green);
+                } else {
+                    self.cprint(ch, Color::Red);
+                }
+            }
+        }
+    }
 
+    /// This function is used to remove the hint once the user starts typing again
+    fn clear_hint(&mut self, validator: &mut InputValidator)
+    {
         self.clear_until_footer();
 
+        if let Some((x, y)) = self.peek_origin.take() {
+            self.goto(x, y);
+        }
+
         validator.end_peek();
     }
 
@@ -615,17 +903,18 @@ impl Display
             validator.undo(delta);
 
             if delta > 0 {
-                self.cursor.move_left(delta as u16);
+                let (x, y) = self.cursor;
+                self.goto(x.saturating_sub(delta as u16), y);
             }
 
-            self.terminal
+            self.backend
                 .clear(ClearType::UntilNewLine)
                 .expect("error clearing rest of line");
         }
     }
 }
 
-impl Drop for Display
+impl<B: Backend> Drop for Display<B>
 {
     fn drop(&mut self)
     {
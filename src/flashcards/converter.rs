@@ -0,0 +1,179 @@
+//! Typed answer converters.
+//!
+//! A back line can declare how its answer should be interpreted (`int`, `float`,
+//! `bool`, `timestamp`, or `timestamp|<fmt>`) instead of being matched character by
+//! character. During validation both the expected answer and the user's input are
+//! parsed through the same `Converter` and compared by value, so equivalent spellings
+//! (`07` vs `7`, a differently laid-out date) are accepted.
+
+use chrono::NaiveDateTime;
+
+/// A declared answer type for a back line, parsed from its markup annotation.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Converter
+{
+    /// Compares as a signed integer.
+    Int,
+    /// Compares as a floating-point number.
+    Float,
+    /// Compares as a boolean (`true`/`false`, `yes`/`no`, `1`/`0`, case-insensitive).
+    Bool,
+    /// Compares as a timestamp, parsed with the given `chrono` format string, or a set
+    /// of common date layouts if no format is given.
+    Timestamp(Option<String>),
+}
+
+impl Converter
+{
+    /// Parses a type annotation such as `int`, `float`, `bool`, `timestamp`, or
+    /// `timestamp|%Y-%m-%d`, returning `None` if `annotation` doesn't name a known type.
+    pub fn parse_annotation(annotation: &str) -> Option<Self>
+    {
+        let mut parts = annotation.trim().splitn(2, '|');
+        let name = parts.next().unwrap_or("");
+        let arg = parts.next().map(str::trim).map(String::from);
+
+        match name {
+            "int" => Some(Converter::Int),
+            "float" => Some(Converter::Float),
+            "bool" => Some(Converter::Bool),
+            "timestamp" => Some(Converter::Timestamp(arg)),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `expected` and `received` parse to the same value under this
+    /// converter. Falls back to an exact string match if either side fails to parse.
+    pub fn values_match(&self, expected: &str, received: &str) -> bool
+    {
+        let (expected, received) = (expected.trim(), received.trim());
+
+        let matched = match self {
+            Converter::Int => {
+                match (expected.parse::<i64>(), received.parse::<i64>()) {
+                    (Ok(a), Ok(b)) => Some(a == b),
+                    _ => None,
+                }
+            }
+            Converter::Float => {
+                match (expected.parse::<f64>(), received.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => Some((a - b).abs() < f64::EPSILON),
+                    _ => None,
+                }
+            }
+            Converter::Bool => {
+                match (parse_bool(expected), parse_bool(received)) {
+                    (Some(a), Some(b)) => Some(a == b),
+                    _ => None,
+                }
+            }
+            Converter::Timestamp(fmt) => {
+                match (parse_timestamp(expected, fmt.as_deref()), parse_timestamp(received, fmt.as_deref())) {
+                    (Some(a), Some(b)) => Some(a == b),
+                    _ => None,
+                }
+            }
+        };
+
+        matched.unwrap_or_else(|| expected == received)
+    }
+}
+
+/// Parses a boolean from any of the common spellings used for yes/no answers.
+fn parse_bool(s: &str) -> Option<bool>
+{
+    match s.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Date/time layouts tried, in order, when no explicit format is given.
+const DEFAULT_TIMESTAMP_FORMATS: &[&str] =
+    &["%Y-%m-%d", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d", "%d.%m.%Y"];
+
+/// Parses `s` as a timestamp using `fmt` if given, or each of
+/// `DEFAULT_TIMESTAMP_FORMATS` in turn.
+fn parse_timestamp(s: &str, fmt: Option<&str>) -> Option<NaiveDateTime>
+{
+    match fmt {
+        Some(fmt) => parse_with_format(s, fmt),
+        None => DEFAULT_TIMESTAMP_FORMATS.iter().find_map(|fmt| parse_with_format(s, fmt)),
+    }
+}
+
+/// Tries `fmt` as a full datetime format first, then as a date-only format (midnight).
+fn parse_with_format(s: &str, fmt: &str) -> Option<NaiveDateTime>
+{
+    NaiveDateTime::parse_from_str(s, fmt)
+        .ok()
+        .or_else(|| chrono::NaiveDate::parse_from_str(s, fmt).ok()?.and_hms_opt(0, 0, 0))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parses_known_annotations()
+    {
+        assert_eq!(Some(Converter::Int), Converter::parse_annotation("int"));
+        assert_eq!(Some(Converter::Float), Converter::parse_annotation("float"));
+        assert_eq!(Some(Converter::Bool), Converter::parse_annotation("bool"));
+        assert_eq!(
+            Some(Converter::Timestamp(Some("%Y-%m-%d".to_string()))),
+            Converter::parse_annotation("timestamp|%Y-%m-%d")
+        );
+        assert_eq!(Some(Converter::Timestamp(None)), Converter::parse_annotation("timestamp"));
+    }
+
+    #[test]
+    fn rejects_unknown_annotations()
+    {
+        assert_eq!(None, Converter::parse_annotation("string"));
+    }
+
+    #[test]
+    fn int_matches_equivalent_spellings()
+    {
+        assert!(Converter::Int.values_match("07", "7"));
+        assert!(!Converter::Int.values_match("7", "8"));
+    }
+
+    #[test]
+    fn float_matches_equivalent_spellings()
+    {
+        assert!(Converter::Float.values_match("1.50", "1.5"));
+    }
+
+    #[test]
+    fn bool_matches_synonyms()
+    {
+        assert!(Converter::Bool.values_match("true", "yes"));
+        assert!(Converter::Bool.values_match("false", "0"));
+        assert!(!Converter::Bool.values_match("true", "no"));
+    }
+
+    #[test]
+    fn timestamp_matches_a_different_but_valid_layout()
+    {
+        let converter = Converter::Timestamp(None);
+        assert!(converter.values_match("2024-01-02", "2024/01/02"));
+    }
+
+    #[test]
+    fn timestamp_uses_the_declared_format()
+    {
+        let converter = Converter::Timestamp(Some("%d.%m.%Y".to_string()));
+        assert!(converter.values_match("02.01.2024", "02.01.2024"));
+    }
+
+    #[test]
+    fn falls_back_to_exact_match_when_unparsable()
+    {
+        assert!(Converter::Int.values_match("seven", "seven"));
+        assert!(!Converter::Int.values_match("seven", "7"));
+    }
+}
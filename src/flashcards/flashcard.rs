@@ -51,7 +51,7 @@ impl Flashcard
             }
             WriteTheLine(lines) => {
                 for line in lines {
-                    hasher.write(line.as_bytes());
+                    hasher.write(line.text.as_bytes());
                 }
             }
         }
@@ -69,7 +69,7 @@ impl Flashcard
         match &self.back {
             WriteTheLine(lines) => {
                 // all lines needs to be validated
-                lines.clone()
+                lines.iter().map(|line| line.text.clone()).collect()
             }
             FillTheBlank(lines) => {
                 // only blanks need to be validated
@@ -89,6 +89,20 @@ impl Flashcard
         }
     }
 
+    /// For each line returned by `get_lines_to_validate`, the `Converter` declared for
+    /// it, if any, so the validator can compare an answer semantically instead of
+    /// verbatim. `FillTheBlank` blanks have no type annotation of their own, so they
+    /// always report `None`.
+    pub fn get_converters_to_validate(&self) -> Vec<Option<Converter>>
+    {
+        use FlashcardBack::*;
+
+        match &self.back {
+            WriteTheLine(lines) => lines.iter().map(|line| line.converter.clone()).collect(),
+            FillTheBlank(lines) => lines.iter().map(|_| None).collect(),
+        }
+    }
+
     // This doesn't work because displaying is different for each flashcard type:
     // - FillTheBlanks: blanks are displayed with underscores.
     // - WriteTheLine: no underscores
@@ -177,7 +191,7 @@ impl Hash for Flashcard
             }
             WriteTheLine(lines) => {
                 for line in lines {
-                    line.as_bytes().hash(state);
+                    line.text.as_bytes().hash(state);
                 }
             }
         }
@@ -1,8 +1,10 @@
 //! A module to represent and build flashcard.
 
+pub mod converter;
 pub mod flashcard;
 pub mod flashcard_factory;
 
+pub use self::converter::Converter;
 pub use self::flashcard::Flashcard;
 
 /// Represents a single line on a flashcard.
@@ -15,6 +17,18 @@ pub struct LinePart(pub String, pub bool, pub usize);
 /// Represents a line that can contain blanks.
 pub type LineWithBlanks = Vec<LinePart>;
 
+/// A line on the back of a `WriteTheLine` card, optionally annotated with a
+/// `Converter` that compares the answer semantically instead of verbatim.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TypedLine
+{
+    /// The literal answer text, as written in the markup.
+    pub text: Line,
+    /// How to compare the user's answer against `text`; `None` falls back to an exact
+    /// string match.
+    pub converter: Option<Converter>,
+}
+
 /// Represents the various types of flashcard backs.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum FlashcardBack
@@ -23,5 +37,5 @@ pub enum FlashcardBack
     FillTheBlank(Vec<LineWithBlanks>),
 
     /// Requires the user to write whole lines.
-    WriteTheLine(Vec<Line>),
+    WriteTheLine(Vec<TypedLine>),
 }
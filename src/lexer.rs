@@ -0,0 +1,288 @@
+//! Pure lexical scanning of flashcard deck lines, kept separate from `cardbox_parser`'s
+//! `ParserState` machine so each can be tested and reasoned about on its own (mirroring
+//! how `rustc_lexer` separates scanning from the parser that drives it).
+//!
+//! `tokenize` never panics and performs no state checks of its own: it just trims and
+//! classifies one line of input into a `Token`, leaving every higher-level decision
+//! (is this marker legal here? does this card have a face yet?) to the caller.
+
+use crate::constants::{MARKUP, MARKUP_COMMENT, MARKUP_ESCAPE, MARKUP_FACE, MARKUP_FENCE, MARKUP_NOTE};
+
+/// A single lexical token, tagged with the 1-based line number and the 1-based column
+/// (byte offset within the raw line) where it starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a>
+{
+    /// The 1-based line number the token came from.
+    pub line: usize,
+    /// The 1-based column of the first non-whitespace character on the line.
+    pub col: usize,
+    /// What the line was classified as.
+    pub kind: TokenKind<'a>,
+}
+
+/// The classification of a deck line, with any markup already stripped and escapes
+/// already resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind<'a>
+{
+    /// A face marker line (`#`, `##`, ...): `level` is the number of `#` characters,
+    /// `text` is the trimmed front text that follows them.
+    FaceMarker
+    {
+        /// The number of leading `#` characters.
+        level: usize,
+        /// The front text after the markers, trimmed.
+        text: &'a str,
+    },
+    /// A note line (`!`), holding the trimmed note text that follows the marker.
+    Note(&'a str),
+    /// A comment line (`%`), to be ignored by the parser.
+    Comment,
+    /// A line that is empty once trimmed.
+    Blank,
+    /// A back-of-card content line, with a leading escape character already resolved.
+    Content(&'a str),
+    /// A verbatim block fence (`~~~` on its own line), opening or closing a region
+    /// whose lines the parser passes through untouched.
+    Fence,
+    /// A line the current markup can't classify.
+    ///
+    /// Every first character is handled by one of the kinds above, so `tokenize` never
+    /// actually produces this today; it exists so a future markup addition has somewhere
+    /// to report "this isn't valid under any rule" instead of silently becoming content.
+    Invalid,
+}
+
+/// Classifies one line of deck source into a `Token`.
+///
+/// `raw_line` may still have surrounding whitespace; `tokenize` trims it before
+/// classifying, the same way `cardbox_parser::parse`'s line loop always has.
+pub fn tokenize(line_no: usize, raw_line: &str) -> Token<'_>
+{
+    let trimmed_start = raw_line.trim_start();
+    let col = raw_line.len() - trimmed_start.len() + 1;
+    let line = trimmed_start.trim_end();
+
+    if line.is_empty() {
+        return Token { line: line_no, col, kind: TokenKind::Blank };
+    }
+
+    if line == MARKUP_FENCE {
+        return Token { line: line_no, col, kind: TokenKind::Fence };
+    }
+
+    // 1st char must exist, so unwrap won't fail ever
+    let first_char = line.chars().nth(0).unwrap();
+
+    let kind = match first_char {
+        MARKUP_FACE => {
+            let level = line.chars().take_while(|c| c == &MARKUP_FACE).count();
+            // `MARKUP_FACE` is a single-byte ASCII char, so `level` is also a valid
+            // byte offset into `line`.
+            let text = line[level..].trim();
+            TokenKind::FaceMarker { level, text }
+        }
+        MARKUP_NOTE => {
+            let text = line[MARKUP_NOTE.len_utf8()..].trim();
+            TokenKind::Note(text)
+        }
+        MARKUP_COMMENT => TokenKind::Comment,
+        _ => {
+            // If the 1st character is the `Escape` character, and actually used for
+            // escaping a markup char ...
+            if first_char == MARKUP_ESCAPE
+                && line.chars().nth(1).map_or(false, |c| MARKUP.contains(&c))
+            {
+                // ... then drop it, and treat the rest of the line as content.
+                TokenKind::Content(&line[MARKUP_ESCAPE.len_utf8()..])
+            } else {
+                TokenKind::Content(line)
+            }
+        }
+    };
+
+    Token { line: line_no, col, kind }
+}
+
+/// Classifies one line of deck source directly from bytes, without decoding it as
+/// UTF-8 first.
+///
+/// The first byte alone is enough to tell a comment or blank line from one that needs
+/// its text retained, since every markup character (`MARKUP_FACE`, `MARKUP_NOTE`,
+/// `MARKUP_COMMENT`, `MARKUP_ESCAPE`) is single-byte ASCII; a byte that starts a
+/// multi-byte UTF-8 sequence can never match one of them. So `Blank`/`Comment` lines,
+/// and the markup bytes themselves, are classified with no validation at all, and
+/// `str::from_utf8` only runs over the span that's actually kept as text.
+pub fn tokenize_bytes(line_no: usize, raw_line: &[u8]) -> Result<Token<'_>, std::str::Utf8Error>
+{
+    let trimmed_start = trim_ascii_start(raw_line);
+    let col = raw_line.len() - trimmed_start.len() + 1;
+    let line = trim_ascii_end(trimmed_start);
+
+    if line.is_empty() {
+        return Ok(Token { line: line_no, col, kind: TokenKind::Blank });
+    }
+
+    if line == MARKUP_FENCE.as_bytes() {
+        return Ok(Token { line: line_no, col, kind: TokenKind::Fence });
+    }
+
+    let first_byte = line[0];
+
+    let kind = match first_byte {
+        b if b == MARKUP_FACE as u8 => {
+            let level = line.iter().take_while(|&&b| b == MARKUP_FACE as u8).count();
+            let text = std::str::from_utf8(trim_ascii(&line[level..]))?;
+            TokenKind::FaceMarker { level, text }
+        }
+        b if b == MARKUP_NOTE as u8 => {
+            let text = std::str::from_utf8(trim_ascii(&line[1..]))?;
+            TokenKind::Note(text)
+        }
+        b if b == MARKUP_COMMENT as u8 => TokenKind::Comment,
+        b if b == MARKUP_ESCAPE as u8
+            && line.get(1).map_or(false, |c| MARKUP.iter().any(|m| *m as u8 == *c)) =>
+        {
+            TokenKind::Content(std::str::from_utf8(&line[1..])?)
+        }
+        _ => TokenKind::Content(std::str::from_utf8(line)?),
+    };
+
+    Ok(Token { line: line_no, col, kind })
+}
+
+fn trim_ascii_start(bytes: &[u8]) -> &[u8]
+{
+    match bytes.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(i) => &bytes[i..],
+        None => &[],
+    }
+}
+
+fn trim_ascii_end(bytes: &[u8]) -> &[u8]
+{
+    match bytes.iter().rposition(|b| !b.is_ascii_whitespace()) {
+        Some(i) => &bytes[..=i],
+        None => &[],
+    }
+}
+
+fn trim_ascii(bytes: &[u8]) -> &[u8]
+{
+    trim_ascii_end(trim_ascii_start(bytes))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn a_blank_line_is_blank()
+    {
+        assert_eq!(TokenKind::Blank, tokenize(1, "   ").kind);
+    }
+
+    #[test]
+    fn a_single_hash_is_a_level_1_face_marker()
+    {
+        let token = tokenize(1, "# Question");
+        assert_eq!(TokenKind::FaceMarker { level: 1, text: "Question" }, token.kind);
+    }
+
+    #[test]
+    fn a_double_hash_is_a_level_2_face_marker()
+    {
+        let token = tokenize(1, "## Question");
+        assert_eq!(TokenKind::FaceMarker { level: 2, text: "Question" }, token.kind);
+    }
+
+    #[test]
+    fn a_bang_line_is_a_note()
+    {
+        let token = tokenize(1, "! some context");
+        assert_eq!(TokenKind::Note("some context"), token.kind);
+    }
+
+    #[test]
+    fn a_percent_line_is_a_comment()
+    {
+        assert_eq!(TokenKind::Comment, tokenize(1, "% a comment").kind);
+    }
+
+    #[test]
+    fn an_ordinary_line_is_content()
+    {
+        assert_eq!(TokenKind::Content("the answer"), tokenize(1, "the answer").kind);
+    }
+
+    #[test]
+    fn an_escaped_markup_char_is_resolved_into_content()
+    {
+        assert_eq!(TokenKind::Content("#foo"), tokenize(1, "\\#foo").kind);
+    }
+
+    #[test]
+    fn an_escape_before_a_non_markup_char_stays_literal()
+    {
+        assert_eq!(TokenKind::Content("\\nothing"), tokenize(1, "\\nothing").kind);
+    }
+
+    #[test]
+    fn col_points_at_the_first_non_whitespace_character()
+    {
+        assert_eq!(5, tokenize(1, "    # Question").col);
+    }
+
+    #[test]
+    fn tokenize_bytes_classifies_a_face_marker_without_decoding_the_whole_line()
+    {
+        let token = tokenize_bytes(1, b"# Question").unwrap();
+        assert_eq!(TokenKind::FaceMarker { level: 1, text: "Question" }, token.kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_classifies_a_comment_line_with_no_allocation()
+    {
+        assert_eq!(TokenKind::Comment, tokenize_bytes(1, b"% a comment").unwrap().kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_classifies_a_blank_line()
+    {
+        assert_eq!(TokenKind::Blank, tokenize_bytes(1, b"   ").unwrap().kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_resolves_an_escaped_markup_char_into_content()
+    {
+        assert_eq!(TokenKind::Content("#foo"), tokenize_bytes(1, b"\\#foo").unwrap().kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_rejects_invalid_utf8_in_content()
+    {
+        assert!(tokenize_bytes(1, &[b'a', 0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn a_triple_tilde_line_is_a_fence()
+    {
+        assert_eq!(TokenKind::Fence, tokenize(1, "~~~").kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_classifies_a_fence_line()
+    {
+        assert_eq!(TokenKind::Fence, tokenize_bytes(1, b"~~~").unwrap().kind);
+    }
+
+    #[test]
+    fn tokenize_bytes_never_validates_a_comment_lines_bytes()
+    {
+        // A comment line carrying invalid UTF-8 after the marker must still classify
+        // as `Comment`, since its text is never read.
+        assert_eq!(TokenKind::Comment, tokenize_bytes(1, &[b'%', 0xff, 0xfe]).unwrap().kind);
+    }
+}
@@ -6,12 +6,19 @@
 mod cardbox_parser;
 mod cli;
 mod common;
+mod config;
 mod constants;
 mod db;
+mod lexer;
 
+pub mod backend;
 pub mod cardbox;
 pub mod display;
 pub mod flashcards;
+pub mod render;
+pub mod scheduler;
+pub mod sm2;
+pub mod sync;
 pub mod validator;
 
 /// Re-export of commonly used types.
@@ -19,8 +26,13 @@ pub mod prelude
 {
     pub use super::cardbox::{Cardbox, Envelope};
     pub use super::cli::Cli;
+    pub use super::db::{FileProgressStore, ProgressStore};
     pub use super::display::Display;
     pub use super::flashcards::Flashcard;
+    pub use super::render::{FlashcardHandler, HtmlHandler, Render};
+    pub use super::scheduler::Scheduler;
+    pub use super::sm2::Sm2Scheduler;
+    pub use super::sync::HttpClient;
     pub use super::validator::{FlashcardValidator, InputValidator};
     pub use crossterm::Color;
 }
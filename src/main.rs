@@ -7,15 +7,24 @@ fn main()
     let mut display = Display::new();
     display.init();
 
-    let mut cardbox = Cardbox::new();
-    cardbox.init(cli.filepath());
+    let store = FileProgressStore::default();
+    #[cfg(feature = "encryption")]
+    let store = store.with_key(cli.key_source());
+
+    let mut scheduler: Box<dyn Scheduler> = if cli.use_sm2() {
+        Box::new(Sm2Scheduler::new(store))
+    } else {
+        let mut cardbox = Cardbox::new(store).expect("error creating cardbox");
+        if let Some(url) = cli.sync_url() {
+            cardbox.set_sync_client(HttpClient::new(url));
+        }
+        Box::new(cardbox)
+    };
+    scheduler.init(cli.filepath());
 
     // Process flashcards until they all reached final stage, or their interval isn't up
     // yet
-    'outer: while let Some((flashcard, current_stage)) = cardbox.next() {
-        //display.print_progress(cardbox.progress());
-        cardbox.display_progress(&mut display);
-
+    'outer: while let Some((flashcard, _current_stage)) = scheduler.next() {
         // Print the front side of the flash card which usually describes the task
         flashcard.display_face(&mut display);
 
@@ -52,13 +61,11 @@ fn main()
         // Optionally print additional notes
         flashcard.display_note(&mut display);
 
-        // If the back of the flashcard was entered correctly, increase its stage,
-        // otherwise reset its stage
+        // Grade the review and let the scheduler move the card's stage accordingly
+        scheduler.grade(card_validator.quality());
         if card_validator.passed() {
-            cardbox.increase_stage(current_stage);
             display.print_passed();
         } else {
-            cardbox.reset_stage(current_stage);
             display.print_failed()
         }
 
@@ -71,5 +78,5 @@ fn main()
         display.redraw();
     }
 
-    cardbox.save();
+    scheduler.save();
 }
@@ -0,0 +1,189 @@
+//! The default `FlashcardHandler`: renders a deck as a single self-study HTML page.
+
+use super::FlashcardHandler;
+use crate::flashcards::{LineWithBlanks, TypedLine};
+
+use std::io::{self, Write};
+
+/// Renders a parsed deck as one HTML page: each card becomes a `<section>`.
+///
+/// `FillTheBlank` blanks render as fillable `<input>` elements by default (`new`), so
+/// the page works as a self-study quiz; `with_static_blanks` renders them as static
+/// `<span class="blank">` placeholders instead, e.g. for printing an answer key.
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlHandler
+{
+    interactive: bool,
+}
+
+impl HtmlHandler
+{
+    /// An interactive page whose blanks are `<input>` elements the reader can type into.
+    pub fn new() -> Self
+    {
+        Self { interactive: true }
+    }
+
+    /// A static page whose blanks render as `<span class="blank">` placeholders
+    /// instead of `<input>` elements.
+    pub fn with_static_blanks() -> Self
+    {
+        Self { interactive: false }
+    }
+}
+
+impl Default for HtmlHandler
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl FlashcardHandler for HtmlHandler
+{
+    fn start_document(&mut self, writer: &mut dyn Write) -> io::Result<()>
+    {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html>")?;
+        writeln!(writer, "<head><meta charset=\"utf-8\"><title>Flashcards</title></head>")?;
+        writeln!(writer, "<body>")
+    }
+
+    fn start_card(&mut self, writer: &mut dyn Write, index: usize) -> io::Result<()>
+    {
+        writeln!(writer, "<section class=\"flashcard\" id=\"card-{}\">", index)
+    }
+
+    fn face(&mut self, writer: &mut dyn Write, face: &str) -> io::Result<()>
+    {
+        writeln!(writer, "<h2 class=\"face\">{}</h2>", escape(face))
+    }
+
+    fn write_the_line(&mut self, writer: &mut dyn Write, line: &TypedLine) -> io::Result<()>
+    {
+        if self.interactive {
+            writeln!(writer, "<p class=\"write-the-line\"><input type=\"text\"></p>")
+        } else {
+            writeln!(
+                writer,
+                "<p class=\"write-the-line\"><span class=\"blank\">{}</span></p>",
+                escape(&line.text)
+            )
+        }
+    }
+
+    fn fill_the_blank_line(
+        &mut self,
+        writer: &mut dyn Write,
+        line: &LineWithBlanks,
+    ) -> io::Result<()>
+    {
+        write!(writer, "<p class=\"fill-the-blank\">")?;
+        for part in line {
+            let text = escape(&part.0);
+            let is_blank = part.1;
+
+            if is_blank && self.interactive {
+                write!(writer, "<input type=\"text\" size=\"{}\"> ", part.0.chars().count().max(1))?;
+            } else if is_blank {
+                write!(writer, "<span class=\"blank\">{}</span> ", text)?;
+            } else {
+                write!(writer, "{} ", text)?;
+            }
+        }
+        writeln!(writer, "</p>")
+    }
+
+    fn note(&mut self, writer: &mut dyn Write, note: &str) -> io::Result<()>
+    {
+        writeln!(writer, "<p class=\"note\">{}</p>", escape(note))
+    }
+
+    fn end_card(&mut self, writer: &mut dyn Write, _index: usize) -> io::Result<()>
+    {
+        writeln!(writer, "</section>")
+    }
+
+    fn end_document(&mut self, writer: &mut dyn Write) -> io::Result<()>
+    {
+        writeln!(writer, "</body>")?;
+        writeln!(writer, "</html>")
+    }
+}
+
+/// Escapes the characters HTML treats specially, so flashcard content can't break out
+/// of its surrounding tag.
+fn escape(text: &str) -> String
+{
+    text.chars().fold(String::with_capacity(text.len()), |mut acc, c| {
+        match c {
+            '&' => acc.push_str("&amp;"),
+            '<' => acc.push_str("&lt;"),
+            '>' => acc.push_str("&gt;"),
+            '"' => acc.push_str("&quot;"),
+            '\'' => acc.push_str("&#39;"),
+            _ => acc.push(c),
+        }
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::flashcards::LinePart;
+
+    fn render_to_string<F: FnOnce(&mut HtmlHandler, &mut Vec<u8>) -> io::Result<()>>(
+        mut handler: HtmlHandler,
+        f: F,
+    ) -> String
+    {
+        let mut buf = Vec::new();
+        f(&mut handler, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn a_card_is_wrapped_in_a_section()
+    {
+        let html = render_to_string(HtmlHandler::new(), |h, w| h.start_card(w, 1));
+        assert!(html.starts_with("<section class=\"flashcard\" id=\"card-1\">"));
+    }
+
+    #[test]
+    fn the_face_is_html_escaped()
+    {
+        let html = render_to_string(HtmlHandler::new(), |h, w| h.face(w, "a < b && b < c"));
+        assert!(html.contains("a &lt; b &amp;&amp; b &lt; c"));
+    }
+
+    #[test]
+    fn an_interactive_blank_renders_as_an_input()
+    {
+        let line: LineWithBlanks = vec![LinePart("answer".into(), true, 0)];
+        let html = render_to_string(HtmlHandler::new(), |h, w| h.fill_the_blank_line(w, &line));
+        assert!(html.contains("<input"));
+        assert!(!html.contains("class=\"blank\""));
+    }
+
+    #[test]
+    fn a_static_blank_renders_as_a_span()
+    {
+        let line: LineWithBlanks = vec![LinePart("answer".into(), true, 0)];
+        let html =
+            render_to_string(HtmlHandler::with_static_blanks(), |h, w| h.fill_the_blank_line(w, &line));
+        assert!(html.contains("<span class=\"blank\">answer</span>"));
+        assert!(!html.contains("<input"));
+    }
+
+    #[test]
+    fn a_non_blank_part_is_rendered_as_plain_text()
+    {
+        let line: LineWithBlanks = vec![LinePart("the".into(), false, 0)];
+        let html = render_to_string(HtmlHandler::new(), |h, w| h.fill_the_blank_line(w, &line));
+        assert!(html.contains("the"));
+        assert!(!html.contains("<input"));
+    }
+}
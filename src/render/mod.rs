@@ -0,0 +1,238 @@
+//! Rendering a parsed deck back out to a document, via a pluggable `FlashcardHandler`.
+//!
+//! `Render` walks a `&[Flashcard]` and calls into a handler for each piece of a card,
+//! the same handler/render split `orgize` uses to turn a parsed document into HTML: the
+//! driver owns the walk, the handler owns the output format. Ship `HtmlHandler` as the
+//! default; a Markdown or Anki-TSV handler is a drop-in `FlashcardHandler` impl away.
+
+pub mod html;
+
+pub use self::html::HtmlHandler;
+
+use crate::flashcards::{Flashcard, FlashcardBack, LineWithBlanks, TypedLine};
+
+use std::io::{self, Write};
+
+/// Callbacks invoked by `Render` as it walks a parsed deck, in the order a card's
+/// content appears: `start_card`, `face`, one `write_the_line`/`fill_the_blank_line`
+/// call per back line, an optional `note`, then `end_card`.
+pub trait FlashcardHandler
+{
+    /// Called once, before the first card, to write any document-level preamble.
+    ///
+    /// Defaults to doing nothing, since most formats don't need one.
+    fn start_document(&mut self, _writer: &mut dyn Write) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    /// Called before a card's other callbacks, with the card's 1-based position in the
+    /// deck.
+    fn start_card(&mut self, writer: &mut dyn Write, index: usize) -> io::Result<()>;
+
+    /// Writes the front of the card.
+    fn face(&mut self, writer: &mut dyn Write, face: &str) -> io::Result<()>;
+
+    /// Writes one `WriteTheLine` back line.
+    fn write_the_line(&mut self, writer: &mut dyn Write, line: &TypedLine) -> io::Result<()>;
+
+    /// Writes one `FillTheBlank` back line.
+    fn fill_the_blank_line(
+        &mut self,
+        writer: &mut dyn Write,
+        line: &LineWithBlanks,
+    ) -> io::Result<()>;
+
+    /// Writes the card's note, if it has one.
+    fn note(&mut self, writer: &mut dyn Write, note: &str) -> io::Result<()>;
+
+    /// Called after a card's other callbacks, with the same 1-based position passed to
+    /// `start_card`.
+    fn end_card(&mut self, writer: &mut dyn Write, index: usize) -> io::Result<()>;
+
+    /// Called once, after the last card, to write any document-level closing.
+    ///
+    /// Defaults to doing nothing, since most formats don't need one.
+    fn end_document(&mut self, _writer: &mut dyn Write) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+/// Drives a `FlashcardHandler` over a parsed deck, writing its output to `writer`.
+pub struct Render<'a, H: FlashcardHandler, W: Write>
+{
+    handler: H,
+    writer: W,
+    cards: &'a [Flashcard],
+}
+
+impl<'a, H: FlashcardHandler, W: Write> Render<'a, H, W>
+{
+    /// Creates a render pass over `cards`, to be driven by `render`.
+    pub fn new(handler: H, writer: W, cards: &'a [Flashcard]) -> Self
+    {
+        Self { handler, writer, cards }
+    }
+
+    /// Walks `cards` in order, calling into the handler for each one and writing
+    /// whatever it produces.
+    pub fn render(mut self) -> io::Result<()>
+    {
+        self.handler.start_document(&mut self.writer)?;
+
+        for (i, card) in self.cards.iter().enumerate() {
+            let index = i + 1;
+
+            self.handler.start_card(&mut self.writer, index)?;
+            self.handler.face(&mut self.writer, &card.face)?;
+
+            match &card.back {
+                FlashcardBack::WriteTheLine(lines) => {
+                    for line in lines {
+                        self.handler.write_the_line(&mut self.writer, line)?;
+                    }
+                }
+                FlashcardBack::FillTheBlank(lines) => {
+                    for line in lines {
+                        self.handler.fill_the_blank_line(&mut self.writer, line)?;
+                    }
+                }
+            }
+
+            if let Some(note) = &card.note {
+                self.handler.note(&mut self.writer, note)?;
+            }
+
+            self.handler.end_card(&mut self.writer, index)?;
+        }
+
+        self.handler.end_document(&mut self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::flashcards::{LinePart, TypedLine};
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A `FlashcardHandler` that just logs which callback fired, so tests can assert on
+    /// call order without depending on any particular output format. The log is shared
+    /// through an `Rc` so it can still be read after `Render::render` consumes the
+    /// handler.
+    #[derive(Clone, Default)]
+    struct LoggingHandler
+    {
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl FlashcardHandler for LoggingHandler
+    {
+        fn start_document(&mut self, _writer: &mut dyn Write) -> io::Result<()>
+        {
+            self.log.borrow_mut().push("start_document".into());
+            Ok(())
+        }
+
+        fn start_card(&mut self, _writer: &mut dyn Write, index: usize) -> io::Result<()>
+        {
+            self.log.borrow_mut().push(format!("start_card({})", index));
+            Ok(())
+        }
+
+        fn face(&mut self, _writer: &mut dyn Write, face: &str) -> io::Result<()>
+        {
+            self.log.borrow_mut().push(format!("face({})", face));
+            Ok(())
+        }
+
+        fn write_the_line(&mut self, _writer: &mut dyn Write, line: &TypedLine) -> io::Result<()>
+        {
+            self.log.borrow_mut().push(format!("write_the_line({})", line.text));
+            Ok(())
+        }
+
+        fn fill_the_blank_line(
+            &mut self,
+            _writer: &mut dyn Write,
+            _line: &LineWithBlanks,
+        ) -> io::Result<()>
+        {
+            self.log.borrow_mut().push("fill_the_blank_line".into());
+            Ok(())
+        }
+
+        fn note(&mut self, _writer: &mut dyn Write, note: &str) -> io::Result<()>
+        {
+            self.log.borrow_mut().push(format!("note({})", note));
+            Ok(())
+        }
+
+        fn end_card(&mut self, _writer: &mut dyn Write, index: usize) -> io::Result<()>
+        {
+            self.log.borrow_mut().push(format!("end_card({})", index));
+            Ok(())
+        }
+
+        fn end_document(&mut self, _writer: &mut dyn Write) -> io::Result<()>
+        {
+            self.log.borrow_mut().push("end_document".into());
+            Ok(())
+        }
+    }
+
+    fn write_the_line_card(subject: &Rc<String>) -> Flashcard
+    {
+        Flashcard {
+            subject: Rc::clone(subject),
+            face: "What is 2+2?".into(),
+            back: FlashcardBack::WriteTheLine(vec![TypedLine { text: "4".into(), converter: None }]),
+            note: Some("basic arithmetic".into()),
+        }
+    }
+
+    fn fill_the_blank_card(subject: &Rc<String>) -> Flashcard
+    {
+        Flashcard {
+            subject: Rc::clone(subject),
+            face: "Fill it in".into(),
+            back: FlashcardBack::FillTheBlank(vec![vec![
+                LinePart("the".into(), false, 0),
+                LinePart("answer".into(), true, 4),
+            ]]),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn render_drives_the_handler_once_per_card_in_order()
+    {
+        let subject = Rc::new(String::from("test"));
+        let cards = vec![write_the_line_card(&subject), fill_the_blank_card(&subject)];
+
+        let handler = LoggingHandler::default();
+        let mut buf = Vec::new();
+        Render::new(handler.clone(), &mut buf, &cards).render().unwrap();
+
+        assert_eq!(
+            vec![
+                "start_document",
+                "start_card(1)",
+                "face(What is 2+2?)",
+                "write_the_line(4)",
+                "note(basic arithmetic)",
+                "end_card(1)",
+                "start_card(2)",
+                "face(Fill it in)",
+                "fill_the_blank_line",
+                "end_card(2)",
+                "end_document",
+            ],
+            *handler.log.borrow()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Defines the `Scheduler` trait: the behavioral contract every card-selection strategy
+//! implements, so different scheduling strategies can be swapped in without touching the
+//! rest of the program.
+
+use crate::flashcards::Flashcard;
+
+/// Decides which flashcard is shown next, and how a review updates its due date.
+///
+/// `Cardbox` implements this with a Leitner box (five stages unless configured
+/// otherwise); `Sm2Scheduler` implements it
+/// with the SM-2 spaced-repetition algorithm.
+pub trait Scheduler
+{
+    /// Loads flashcards from `path` and merges them with any existing progress.
+    fn init(&mut self, path: &str);
+
+    /// Returns the next flashcard that is due for review, along with a scheduler-defined
+    /// measure of how far along it is (a Leitner stage, an SM-2 repetition count, ...).
+    fn next(&self) -> Option<(&Flashcard, usize)>;
+
+    /// Records the outcome of reviewing the flashcard currently returned by `next()`.
+    ///
+    /// `quality` is the user's recall grade, from 0 (complete blackout) to 5 (perfect
+    /// recall); a grade below 3 counts as a failed review.
+    fn grade(&mut self, quality: u8);
+
+    /// Persists the current progress.
+    fn save(&self);
+
+    /// Returns the number of flashcards this scheduler is tracking.
+    fn size(&self) -> usize;
+}
@@ -0,0 +1,119 @@
+//! An SM-2 (SuperMemo) based `Scheduler`, offering an adaptive per-card review interval
+//! instead of `Cardbox`'s fixed per-stage Leitner cooldowns.
+
+use crate::cardbox_parser;
+use crate::common::time;
+use crate::db::{self, ProgressStore, Stage};
+use crate::flashcards::Flashcard;
+use crate::scheduler::Scheduler;
+use crate::validator::FlashcardValidator;
+
+use std::collections::HashMap;
+
+/// Quality grade recorded for a review that failed outright.
+const GRADE_FAIL: u8 = 2;
+
+/// Quality grade recorded for a review that passed outright.
+const GRADE_PASS: u8 = 4;
+
+/// An SM-2 based scheduler: every flashcard keeps its own easiness factor, repetition
+/// count and interval in days, so cards the user knows well drift to weeks-long
+/// intervals while ones they struggle with keep resurfacing daily.
+pub struct Sm2Scheduler
+{
+    cards: Vec<Flashcard>,
+    progress: HashMap<u64, Stage>,
+    store: Box<dyn ProgressStore>,
+}
+
+impl Sm2Scheduler
+{
+    /// Creates a new SM-2 scheduler, loading any existing progress through `store`.
+    pub fn new(store: impl ProgressStore + 'static) -> Self
+    {
+        let store: Box<dyn ProgressStore> = Box::new(store);
+        let progress = store.load().unwrap_or_default();
+
+        Self { cards: Vec::new(), progress, store }
+    }
+
+    /// Maps a pass/fail outcome to an SM-2 quality grade.
+    ///
+    /// `db::review` grows or resets the interval depending on whether `quality` clears
+    /// the "correct recall" threshold of 3 (the exact grade also adjusts the easiness
+    /// factor), so a plain pass/fail is mapped to a representative grade on either side
+    /// of it.
+    pub fn quality_from_passed(passed: bool) -> u8
+    {
+        if passed {
+            GRADE_PASS
+        } else {
+            GRADE_FAIL
+        }
+    }
+
+    /// Maps a `FlashcardValidator`'s typo and hint counters to an SM-2 quality grade.
+    ///
+    /// Prefer this over `quality_from_passed` whenever a validator is available: it lets
+    /// a clean answer (grade 5) grow the interval faster than one the user only passed
+    /// after a correction or a hint (grade 3), instead of treating every pass alike.
+    pub fn quality_from_validator(validator: &FlashcardValidator) -> u8
+    {
+        validator.quality()
+    }
+}
+
+impl Scheduler for Sm2Scheduler
+{
+    /// # Panics
+    ///
+    /// `Scheduler::init` is infallible, so a deck file that fails to parse panics with
+    /// the first reported `cardbox_parser::ParseError` instead of returning one.
+    fn init(&mut self, path: &str)
+    {
+        self.cards = cardbox_parser::parse_from_file(path).expect("error parsing deck file");
+    }
+
+    fn next(&self) -> Option<(&Flashcard, usize)>
+    {
+        let now = time::get_unix_time_millis();
+        let due = db::due_cards(&self.progress, now);
+
+        self.cards
+            .iter()
+            .filter(|card| {
+                let hash = card.get_hash();
+                due.contains(&hash) || !self.progress.contains_key(&hash)
+            })
+            .min_by_key(|card| {
+                self.progress.get(&card.get_hash()).map(|stage| stage.timestamp_ms).unwrap_or(0)
+            })
+            .map(|card| {
+                let reps = self.progress.get(&card.get_hash()).map(|s| s.reps as usize).unwrap_or(0);
+                (card, reps)
+            })
+    }
+
+    fn grade(&mut self, quality: u8)
+    {
+        let hash = match Scheduler::next(self) {
+            Some((card, _)) => card.get_hash(),
+            None => return,
+        };
+
+        let now = time::get_unix_time_millis();
+        let mut stage = self.progress.remove(&hash).unwrap_or_default();
+        db::review(&mut stage, quality, now);
+        self.progress.insert(hash, stage);
+    }
+
+    fn save(&self)
+    {
+        self.store.save(&self.progress).expect("error saving progress database");
+    }
+
+    fn size(&self) -> usize
+    {
+        self.cards.len()
+    }
+}
@@ -0,0 +1,197 @@
+//! Syncing flashcard progress with a remote server.
+//!
+//! Mirrors the sync/async client split: a blocking `SyncClient` for callers that are
+//! happy to wait for a round-trip and retry on failure, and a fire-and-forget
+//! `AsyncClient` for callers that just want to kick a push off in the background.
+
+use crate::db::{self, Stage};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io::Read;
+use std::pin::Pin;
+
+/// Errors that can occur while syncing progress with a remote server.
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent at all (e.g. no connection).
+    Transport(String),
+    /// The server was reached, but responded with an error.
+    Server(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Transport(msg) => write!(f, "error reaching sync server: {}", msg),
+            Error::Server(msg) => write!(f, "sync server returned an error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A blocking client that pushes and pulls the progress database to/from a remote
+/// server, retrying transient failures before giving up.
+pub trait SyncClient {
+    /// Pushes the local progress database to the server.
+    fn push(&self, db: &HashMap<u64, Stage>) -> Result<(), Error>;
+
+    /// Pulls the progress database from the server.
+    fn pull(&self) -> Result<HashMap<u64, Stage>, Error>;
+}
+
+/// A non-blocking client that fires a push without waiting for the server to
+/// acknowledge it.
+pub trait AsyncClient {
+    /// Pushes the local progress database to the server in the background.
+    fn push(
+        &self,
+        db: HashMap<u64, Stage>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+}
+
+/// Reconciles a local and a remote progress database.
+///
+/// For each hash the `Stage` with the newest `timestamp_ms` wins; if both sides last
+/// reviewed the card at the same time, the one with the higher `reps` wins instead.
+pub fn merge(
+    local: HashMap<u64, Stage>,
+    remote: HashMap<u64, Stage>,
+) -> HashMap<u64, Stage> {
+    let mut merged = local;
+
+    for (hash, remote_stage) in remote {
+        let winner = match merged.remove(&hash) {
+            Some(local_stage) => {
+                if remote_stage.timestamp_ms != local_stage.timestamp_ms {
+                    if remote_stage.timestamp_ms > local_stage.timestamp_ms {
+                        remote_stage
+                    } else {
+                        local_stage
+                    }
+                } else if remote_stage.reps >= local_stage.reps {
+                    remote_stage
+                } else {
+                    local_stage
+                }
+            }
+            None => remote_stage,
+        };
+
+        merged.insert(hash, winner);
+    }
+
+    merged
+}
+
+/// The first `SyncClient` backend, talking to a remote server over plain HTTP. It does
+/// not implement `AsyncClient`.
+pub struct HttpClient {
+    base_url: String,
+    max_retries: u32,
+}
+
+impl HttpClient {
+    /// Creates a client pointed at `base_url`, retrying a failed push/pull up to 3
+    /// times before giving up.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self { base_url: base_url.into(), max_retries: 3 }
+    }
+
+    fn push_once(&self, db: &HashMap<u64, Stage>) -> Result<(), Error> {
+        let body = db::encode_text(db);
+        ureq::post(&format!("{}/progress", self.base_url))
+            .send_bytes(&body)
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        Ok(())
+    }
+
+    fn pull_once(&self) -> Result<HashMap<u64, Stage>, Error> {
+        let response = ureq::get(&format!("{}/progress", self.base_url))
+            .call()
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        db::decode_text(&body).map_err(|e| Error::Server(e.to_string()))
+    }
+}
+
+impl SyncClient for HttpClient {
+    fn push(&self, db: &HashMap<u64, Stage>) -> Result<(), Error> {
+        let mut last_error = None;
+
+        for _ in 0..=self.max_retries {
+            match self.push_once(db) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.expect("at least one attempt was made"))
+    }
+
+    fn pull(&self) -> Result<HashMap<u64, Stage>, Error> {
+        let mut last_error = None;
+
+        for _ in 0..=self.max_retries {
+            match self.pull_once() {
+                Ok(db) => return Ok(db),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.expect("at least one attempt was made"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(timestamp_ms: u64, reps: u32) -> Stage {
+        Stage { timestamp_ms, reps, ..Stage::default() }
+    }
+
+    #[test]
+    fn merge_prefers_the_newer_timestamp() {
+        let mut local = HashMap::new();
+        local.insert(1, stage(100, 0));
+
+        let mut remote = HashMap::new();
+        remote.insert(1, stage(200, 0));
+
+        let merged = merge(local, remote);
+        assert_eq!(200, merged[&1].timestamp_ms);
+    }
+
+    #[test]
+    fn merge_breaks_a_timestamp_tie_with_reps() {
+        let mut local = HashMap::new();
+        local.insert(1, stage(100, 1));
+
+        let mut remote = HashMap::new();
+        remote.insert(1, stage(100, 4));
+
+        let merged = merge(local, remote);
+        assert_eq!(4, merged[&1].reps);
+    }
+
+    #[test]
+    fn merge_keeps_entries_only_found_on_one_side() {
+        let mut local = HashMap::new();
+        local.insert(1, stage(100, 0));
+
+        let mut remote = HashMap::new();
+        remote.insert(2, stage(200, 0));
+
+        let merged = merge(local, remote);
+        assert_eq!(2, merged.len());
+    }
+}
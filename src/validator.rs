@@ -18,7 +18,7 @@
 //! assert_eq!(12, v.length());
 //! ```
 
-use crate::flashcards::Flashcard;
+use crate::flashcards::{Converter, Flashcard};
 
 /// TODO: rename to PeekMode
 #[derive(Clone)]
@@ -52,16 +52,41 @@ pub struct InputValidator
 
     /// Wether the received characters all match the expected characters.
     passed: bool,
+
+    /// How to compare `expected` against what the user typed; `None` falls back to the
+    /// character-by-character comparison `received` already performs.
+    converter: Option<Converter>,
+
+    /// Every character entered so far, regardless of position. Tracked alongside
+    /// `received` because a `Converter` may accept an answer of a different length than
+    /// `expected` (e.g. `07` vs `7`).
+    entered: String,
+
+    /// Number of characters checked that didn't match `expected` at their position.
+    ///
+    /// Used by `quality()` (and, downstream, an SM-2 `Scheduler`) to tell a clean answer
+    /// apart from one the user had to correct along the way.
+    num_typos: usize,
+
+    /// Number of characters revealed through `peek()`.
+    num_hints: usize,
 }
 
 impl InputValidator
 {
-    /// Creates a new instance from the `expected` characters.
+    /// Creates a new instance from the `expected` characters, matched verbatim.
     ///
     /// TODO: semantically an InputValidator shouldn't have to care about what is
     /// displayed, so remove that.
     pub fn new(expected: &str) -> Self
 //pub fn new(expected: &str, displayed: &str) -> Self
+    {
+        Self::with_converter(expected, None)
+    }
+
+    /// Creates a new instance from the `expected` answer, comparing it through
+    /// `converter` instead of verbatim when one is given.
+    pub fn with_converter(expected: &str, converter: Option<Converter>) -> Self
     {
         let expected = expected.chars().collect::<Vec<_>>();
         let length = expected.len();
@@ -76,6 +101,10 @@ impl InputValidator
             length,
             hint_mode: HintMode::Inactive,
             passed,
+            converter,
+            entered: String::new(),
+            num_typos: 0,
+            num_hints: 0,
         }
     }
 }
@@ -84,8 +113,20 @@ impl InputValidator
 {
     /// Checks the given character against the corresponding character of the expected
     /// string, and increases the index.
+    ///
+    /// When a `Converter` is declared, position-by-position matching doesn't apply (the
+    /// final answer may have a different length than `expected`), so every keystroke is
+    /// accepted provisionally; `happy()` judges the accumulated `entered` string once it
+    /// parses out to the same value as `expected`.
     pub fn check(&mut self, c: char) -> bool
     {
+        self.entered.push(c);
+
+        if self.converter.is_some() {
+            self.index += 1;
+            return true;
+        }
+
         if self.index >= self.length {
             return false;
         }
@@ -95,6 +136,10 @@ impl InputValidator
         let is_valid = self.expected[index] == c;
         self.received[index] = is_valid;
 
+        if !is_valid {
+            self.num_typos += 1;
+        }
+
         is_valid
     }
 
@@ -103,6 +148,7 @@ impl InputValidator
     {
         self.index = 0;
         self.received.iter_mut().for_each(|r| *r = false);
+        self.entered.clear();
     }
 
     /// Undoes the last number of validation steps.
@@ -114,20 +160,28 @@ impl InputValidator
 
         for _ in 0..num {
             self.index -= 1;
-            self.received[self.index] = false;
+            self.entered.pop();
+            if self.converter.is_none() {
+                self.received[self.index] = false;
+            }
         }
     }
 
-    /// Returns `true` if the user has correctly entered all characters.
+    /// Returns `true` if the user has entered a correct answer: every character matches
+    /// `expected` at its position, or, when a `Converter` is declared, `entered` parses
+    /// to the same value as `expected`.
     pub fn happy(&self) -> bool
     {
-        self.received.iter().all(|r| *r)
+        match &self.converter {
+            Some(converter) => converter.values_match(&self.expected(), &self.entered),
+            None => self.received.iter().all(|r| *r),
+        }
     }
 
     /// Returns `true` if the validator is still accepting more characters.
     pub fn accepts(&self) -> bool
     {
-        self.index < self.length
+        self.converter.is_some() || self.index < self.length
     }
 
     /// Activates the hint mode, and returns a hint/part of the flashcard back.
@@ -139,12 +193,14 @@ impl InputValidator
         match self.hint_mode {
             HintMode::Inactive => {
                 self.hint_mode = HintMode::Active(self.index);
+                self.num_hints += 1;
                 return Some(self.expected[self.index]);
             }
             HintMode::Active(index) => {
                 self.passed = false;
                 if index < self.length - 1 {
                     self.hint_mode = HintMode::Active(index + 1);
+                    self.num_hints += 1;
                     return Some(self.expected[index + 1]);
                 }
             }
@@ -196,6 +252,46 @@ impl InputValidator
         self.passed
     }
 
+    /// Marks this line as failed, regardless of what has been entered so far.
+    ///
+    /// Used to auto-fail a flashcard whose time limit ran out before the user finished
+    /// typing.
+    pub fn fail(&mut self)
+    {
+        self.passed = false;
+    }
+
+    /// Returns the number of characters checked that didn't match `expected` at their
+    /// position.
+    pub fn num_typos(&self) -> usize
+    {
+        self.num_typos
+    }
+
+    /// Returns the number of characters revealed through `peek()`.
+    pub fn num_hints(&self) -> usize
+    {
+        self.num_hints
+    }
+
+    /// Returns a recall-quality grade from 0 (complete blackout) to 5 (perfect recall),
+    /// suitable for `Scheduler::grade`.
+    ///
+    /// A clean, unaided answer grades 5; a passed answer that needed a typo corrected or
+    /// a hint peeked at grades 3; anything that didn't pass grades below 3, lower still
+    /// if hints were used along the way.
+    pub fn quality(&self) -> u8
+    {
+        if !self.passed {
+            return if self.num_hints > 0 { 1 } else { 0 };
+        }
+        if self.num_typos == 0 && self.num_hints == 0 {
+            5
+        } else {
+            3
+        }
+    }
+
     /*
     pub fn context(&self) -> String
     {
@@ -222,6 +318,7 @@ impl FlashcardValidator
     {
         //let lines_to_display = flashcard.get_lines_to_display();
         let lines_to_validate = flashcard.get_lines_to_validate();
+        let converters_to_validate = flashcard.get_converters_to_validate();
 
         let length = lines_to_validate.len();
 
@@ -231,8 +328,8 @@ impl FlashcardValidator
             validators.push(InputValidator::new(&validate, &display));
         }
         */
-        for validate in lines_to_validate {
-            validators.push(InputValidator::new(&validate));
+        for (validate, converter) in lines_to_validate.into_iter().zip(converters_to_validate) {
+            validators.push(InputValidator::with_converter(&validate, converter));
         }
 
         Self { validators, index: 0, length }
@@ -261,6 +358,13 @@ impl FlashcardValidator
     {
         self.validators.iter().all(|v| v.passed)
     }
+
+    /// Returns the overall recall-quality grade: the lowest grade among the contained
+    /// `InputValidator`s, since one badly-recalled line should drag down the whole card.
+    pub fn quality(&self) -> u8
+    {
+        self.validators.iter().map(InputValidator::quality).min().unwrap_or(5)
+    }
 }
 
 /*
@@ -465,6 +569,42 @@ mod tests
         assert_eq!(Some(3), v.first_incorrect());
     }
 
+    #[test]
+    fn quality_is_5_for_a_clean_answer()
+    {
+        let mut v = InputValidator::new("hello");
+        v.check('h');
+        v.check('e');
+        v.check('l');
+        v.check('l');
+        v.check('o');
+        assert_eq!(5, v.quality());
+    }
+
+    #[test]
+    fn quality_is_3_for_a_passed_answer_with_a_typo()
+    {
+        let mut v = InputValidator::new("hello");
+        v.check('h');
+        v.check('3');
+        v.undo(1);
+        v.check('e');
+        v.check('l');
+        v.check('l');
+        v.check('o');
+        assert!(v.happy());
+        assert_eq!(1, v.num_typos());
+        assert_eq!(3, v.quality());
+    }
+
+    #[test]
+    fn quality_is_below_3_for_a_failed_answer()
+    {
+        let mut v = InputValidator::new("hello");
+        v.fail();
+        assert!(v.quality() < 3);
+    }
+
     #[test]
     fn get_expected()
     {
@@ -472,6 +612,33 @@ mod tests
         assert_eq!("hello", &v.expected());
     }
 
+    #[test]
+    fn typed_validator_accepts_an_equivalent_spelling_of_a_different_length()
+    {
+        let mut v = InputValidator::with_converter("07", Some(Converter::Int));
+        assert!(!v.happy());
+        v.check('7');
+        assert!(v.happy());
+    }
+
+    #[test]
+    fn typed_validator_rejects_a_non_equivalent_answer()
+    {
+        let mut v = InputValidator::with_converter("07", Some(Converter::Int));
+        v.check('8');
+        assert!(!v.happy());
+    }
+
+    #[test]
+    fn typed_validator_keeps_accepting_input_past_the_expected_length()
+    {
+        let mut v = InputValidator::with_converter("7", Some(Converter::Int));
+        v.check('0');
+        assert!(v.accepts());
+        v.check('7');
+        assert!(v.happy());
+    }
+
     #[test]
     fn list_validator_is_happy()
     {